@@ -0,0 +1,280 @@
+use alloy::{
+    primitives::{Bytes, B256},
+    providers::Provider,
+};
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+
+use crate::inclusion_boost::{
+    delegation::Delegation,
+    error::InclusionListBoostError,
+    protection::Interchange,
+    satisfaction::{check_header, Satisfaction},
+    types::{CachedInclusionList, InclusionListType, ListKey, ListedTransaction, SlotOverrides, Transaction},
+};
+
+use super::ApiState;
+
+/// Which of the slot's lists a request is about, the spot list unless `?type=forward`
+#[derive(Debug, Default, Deserialize)]
+pub struct ListQuery {
+    #[serde(default, rename = "type")]
+    il_type: InclusionListType,
+}
+
+#[derive(Debug, Serialize)]
+struct CandidateResponse {
+    slot: u64,
+    inclusion_list_type: InclusionListType,
+    inclusion_list: Option<CachedInclusionList>,
+    overrides: SlotOverrides,
+}
+
+/// Either the hash of a transaction known to the execution node or a raw signed transaction
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinRequest {
+    TxHash(B256),
+    Raw(Bytes),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RemoveRequest {
+    tx_hash: B256,
+}
+
+/// Gas usage of a header offered for the slot
+#[derive(Debug, Deserialize)]
+pub struct HeaderCheckRequest {
+    gas_limit: u128,
+    gas_used: u128,
+}
+
+#[derive(Debug, Serialize)]
+struct HeaderCheckResponse {
+    slot: u64,
+    conditional: bool,
+    /// `None` when the header alone can't tell and the payload has to be checked
+    satisfaction: Option<Satisfaction>,
+}
+
+#[derive(Debug, Serialize)]
+struct DelegationsResponse {
+    delegations: Vec<Delegation>,
+    pending_revocations: Vec<Delegation>,
+}
+
+/// Rejects any admin request without `Authorization: Bearer <admin_token>`
+pub async fn authenticate(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Response {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match (token, state.admin_token.as_deref()) {
+        // Constant time, so response timing doesn't reveal how much of a guess was right
+        (Some(token), Some(expected)) if bool::from(token.as_bytes().ct_eq(expected.as_bytes())) => {
+            next.run(request).await
+        }
+        _ => {
+            tracing::warn!(path = %request.uri().path(), "Rejected unauthenticated admin request");
+            StatusCode::UNAUTHORIZED.into_response()
+        }
+    }
+}
+
+pub async fn handle_get_inclusion_list(
+    State(state): State<ApiState>,
+    Path(slot): Path<u64>,
+    Query(query): Query<ListQuery>,
+) -> Response {
+    let key = (slot, query.il_type);
+    if let Err(response) = ensure_upcoming_owned_slot(&state, key) {
+        return response;
+    }
+
+    let inclusion_list = state.cache.inclusion_list_cache.read().get(&key).cloned();
+    let overrides = state.cache.overrides.read().get(&key).cloned().unwrap_or_default();
+
+    Json(CandidateResponse {
+        slot,
+        inclusion_list_type: query.il_type,
+        inclusion_list,
+        overrides,
+    })
+    .into_response()
+}
+
+pub async fn handle_pin_transaction(
+    State(state): State<ApiState>,
+    Path(slot): Path<u64>,
+    Query(query): Query<ListQuery>,
+    Json(request): Json<PinRequest>,
+) -> Response {
+    let key = (slot, query.il_type);
+    if let Err(response) = ensure_upcoming_owned_slot(&state, key) {
+        return response;
+    }
+
+    let transaction = match resolve_pin_request(&state, request).await {
+        Ok(Some(transaction)) => transaction,
+        Ok(None) => return (StatusCode::NOT_FOUND, "transaction not found").into_response(),
+        Err(err) => return (StatusCode::BAD_REQUEST, format!("{err:?}")).into_response(),
+    };
+
+    tracing::info!(slot, il_type = ?query.il_type, tx_hash = ?transaction.tx_hash, "Admin pinned transaction");
+    state.cache.pin_transaction(key, ListedTransaction::from(&transaction));
+
+    StatusCode::OK.into_response()
+}
+
+pub async fn handle_remove_transaction(
+    State(state): State<ApiState>,
+    Path(slot): Path<u64>,
+    Query(query): Query<ListQuery>,
+    Json(request): Json<RemoveRequest>,
+) -> Response {
+    let key = (slot, query.il_type);
+    if let Err(response) = ensure_upcoming_owned_slot(&state, key) {
+        return response;
+    }
+
+    tracing::info!(slot, il_type = ?query.il_type, tx_hash = ?request.tx_hash, "Admin removed transaction");
+    state.cache.remove_transaction(key, request.tx_hash);
+
+    StatusCode::OK.into_response()
+}
+
+/// Whether a header satisfies the slot's list without looking at its payload
+pub async fn handle_check_header(
+    State(state): State<ApiState>,
+    Path(slot): Path<u64>,
+    Query(query): Query<ListQuery>,
+    Json(request): Json<HeaderCheckRequest>,
+) -> Response {
+    let Some(cached) = state.cache.inclusion_list_cache.read().get(&(slot, query.il_type)).cloned() else {
+        return (StatusCode::NOT_FOUND, "no inclusion list for slot").into_response();
+    };
+
+    let conditional = cached.inclusion_list.conditional;
+    let satisfaction = check_header(&cached.transactions, conditional, request.gas_limit, request.gas_used);
+
+    Json(HeaderCheckResponse {
+        slot,
+        conditional,
+        satisfaction,
+    })
+    .into_response()
+}
+
+pub async fn handle_get_delegations(State(state): State<ApiState>) -> Response {
+    let delegations = state.cache.delegations.read();
+
+    Json(DelegationsResponse {
+        delegations: delegations.delegations(),
+        pending_revocations: delegations.pending_revocations(),
+    })
+    .into_response()
+}
+
+/// Revoke every delegation of `slot`, the sidecar sends the revocations on the next head
+pub async fn handle_revoke_delegation(State(state): State<ApiState>, Path(slot): Path<u64>) -> Response {
+    tracing::info!(slot, "Admin revoked delegation");
+    state.cache.delegations.write().revoke_slot(slot);
+
+    StatusCode::OK.into_response()
+}
+
+/// Every signed inclusion list and delegation root in the interchange format
+pub async fn handle_export_protection(State(state): State<ApiState>) -> Response {
+    Json(state.cache.protection.read().export()).into_response()
+}
+
+/// Merge a signing history exported by another instance, before it stops signing
+pub async fn handle_import_protection(
+    State(state): State<ApiState>,
+    Json(interchange): Json<Interchange>,
+) -> Response {
+    match state.cache.protection.write().import(interchange) {
+        Ok(imported) => {
+            tracing::info!(imported, "Admin imported signing protection history");
+            Json(serde_json::json!({ "imported": imported })).into_response()
+        }
+        Err(err) => (StatusCode::BAD_REQUEST, format!("{err:?}")).into_response(),
+    }
+}
+
+pub async fn handle_freeze(
+    State(state): State<ApiState>,
+    Path(slot): Path<u64>,
+    Query(query): Query<ListQuery>,
+) -> Response {
+    set_frozen(state, (slot, query.il_type), true)
+}
+
+pub async fn handle_unfreeze(
+    State(state): State<ApiState>,
+    Path(slot): Path<u64>,
+    Query(query): Query<ListQuery>,
+) -> Response {
+    set_frozen(state, (slot, query.il_type), false)
+}
+
+fn set_frozen(state: ApiState, key: ListKey, frozen: bool) -> Response {
+    if let Err(response) = ensure_upcoming_owned_slot(&state, key) {
+        return response;
+    }
+
+    tracing::info!(slot = key.0, il_type = ?key.1, frozen, "Admin changed inclusion list freeze");
+    state.cache.set_frozen(key, frozen);
+
+    StatusCode::OK.into_response()
+}
+
+/// Overrides only make sense for lists one of our keys is still going to sign, a forward list
+/// is signed by the proposer of the slot before it
+fn ensure_upcoming_owned_slot(state: &ApiState, (slot, il_type): ListKey) -> Result<(), Response> {
+    let status = state.status.snapshot();
+    let proposer_slot = match il_type {
+        InclusionListType::Spot => slot,
+        InclusionListType::Forward => slot.saturating_sub(1),
+    };
+
+    if status.last_head_slot.is_some_and(|head_slot| slot <= head_slot) {
+        return Err((StatusCode::BAD_REQUEST, "slot already passed").into_response());
+    }
+
+    if !status.upcoming_proposals.iter().any(|proposal| proposal.slot == proposer_slot) {
+        return Err((StatusCode::BAD_REQUEST, "slot is not proposed by a managed validator").into_response());
+    }
+
+    Ok(())
+}
+
+async fn resolve_pin_request(
+    state: &ApiState,
+    request: PinRequest,
+) -> Result<Option<Transaction>, InclusionListBoostError> {
+    match request {
+        PinRequest::Raw(raw) => Transaction::from_raw(raw).map(Some),
+        PinRequest::TxHash(tx_hash) => Ok(state
+            .mempool
+            .provider()
+            .get_transaction_by_hash(tx_hash)
+            .await?
+            .map(|tx| state.mempool.from_node(tx))),
+    }
+}
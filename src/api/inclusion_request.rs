@@ -0,0 +1,79 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    time::{Duration, Instant},
+};
+
+use alloy::primitives::{Bytes, B256};
+use axum::{
+    extract::{ConnectInfo, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use parking_lot::Mutex;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use super::ApiState;
+
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize)]
+pub struct InclusionRequestBody {
+    /// EIP-2718 encoded signed transaction
+    tx: Bytes,
+}
+
+#[derive(Debug, Serialize)]
+struct InclusionRequestResponse {
+    tx_hash: B256,
+}
+
+/// Fixed window rate limiter keyed by client ip
+#[derive(Debug)]
+pub struct RateLimiter {
+    max_requests: u32,
+    windows: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests: u32) -> Self {
+        Self {
+            max_requests,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns false once `ip` used up its requests for the current window
+    fn check(&self, ip: IpAddr) -> bool {
+        let mut windows = self.windows.lock();
+        let now = Instant::now();
+        windows.retain(|_, (started, _)| now.duration_since(*started) < RATE_LIMIT_WINDOW);
+
+        let (_, count) = windows.entry(ip).or_insert((now, 0));
+        *count += 1;
+        *count <= self.max_requests
+    }
+}
+
+/// Lets anyone ask our proposers to include a transaction they believe is being censored
+pub async fn handle_post_inclusion_request(
+    State(state): State<ApiState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(request): Json<InclusionRequestBody>,
+) -> Response {
+    if !state.rate_limiter.check(addr.ip()) {
+        return StatusCode::TOO_MANY_REQUESTS.into_response();
+    }
+
+    match state.cache.inclusion_requests.submit(state.mempool.provider(), request.tx).await {
+        Ok(tx_hash) => {
+            tracing::info!(?tx_hash, client = %addr.ip(), "Queued inclusion request");
+            (StatusCode::ACCEPTED, Json(InclusionRequestResponse { tx_hash })).into_response()
+        }
+        Err(rejection) => {
+            tracing::info!(client = %addr.ip(), %rejection, "Rejected inclusion request");
+            (StatusCode::BAD_REQUEST, rejection.to_string()).into_response()
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use axum::{
+    extract::State,
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
+};
+use prometheus::{Encoder, IntGaugeVec, Opts, Registry, TextEncoder, TEXT_FORMAT};
+use reqwest::StatusCode;
+
+use crate::status::ModuleStatus;
+
+use super::ApiState;
+
+/// Prometheus metrics derived from the current module status
+pub async fn handle_get_metrics(State(state): State<ApiState>) -> Response {
+    match encode_metrics(&state.status) {
+        Ok(body) => ([(CONTENT_TYPE, TEXT_FORMAT)], body).into_response(),
+        Err(err) => {
+            tracing::error!(?err, "Failed to encode metrics");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+fn encode_metrics(status: &ModuleStatus) -> Result<String, prometheus::Error> {
+    let registry = Registry::new();
+    let sidecar = status.snapshot();
+
+    let builder_blocks = builder_gauge("il_boost_builder_blocks", "Blocks built in the censorship window")?;
+    let builder_blocks_with_skips = builder_gauge(
+        "il_boost_builder_blocks_with_skips",
+        "Blocks in the censorship window that left out a transaction that could have fit",
+    )?;
+    let builder_skipped_transactions = builder_gauge(
+        "il_boost_builder_skipped_transactions",
+        "Transactions that could have fit but were left out over the censorship window",
+    )?;
+
+    for report in &sidecar.builder_skips {
+        let labels = [report.builder.as_str()];
+        builder_blocks.with_label_values(&labels).set(report.blocks as i64);
+        builder_blocks_with_skips.with_label_values(&labels).set(report.blocks_with_skips as i64);
+        builder_skipped_transactions.with_label_values(&labels).set(report.skipped_transactions as i64);
+    }
+
+    registry.register(Box::new(builder_blocks))?;
+    registry.register(Box::new(builder_blocks_with_skips))?;
+    registry.register(Box::new(builder_skipped_transactions))?;
+
+    let mut buffer = vec![];
+    TextEncoder::new().encode(&registry.gather(), &mut buffer)?;
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+fn builder_gauge(name: &str, help: &str) -> Result<IntGaugeVec, prometheus::Error> {
+    IntGaugeVec::new(Opts::new(name, help), &["builder"])
+}
@@ -0,0 +1,75 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    middleware,
+    routing::{get, post},
+    Router,
+};
+use inclusion_request::RateLimiter;
+use tokio::net::TcpListener;
+
+use crate::{
+    inclusion_boost::{error::InclusionListBoostError, mempool::MempoolSources, types::InclusionBoostCache},
+    status::ModuleStatus,
+};
+
+pub mod admin;
+pub mod inclusion_request;
+pub mod metrics;
+pub mod status;
+
+/// State shared by every handler of the module's HTTP API
+#[derive(Clone)]
+pub struct ApiState {
+    pub status: Arc<ModuleStatus>,
+    pub beacon_api: String,
+    /// Execution nodes, requests go to the first one that answered its last mempool poll
+    pub mempool: Arc<MempoolSources>,
+    pub cache: Arc<InclusionBoostCache>,
+    /// Admin routes are only served when a token is configured
+    pub admin_token: Option<String>,
+    pub rate_limiter: Arc<RateLimiter>,
+}
+
+/// Serve the module's own HTTP API, separate from the PBS server
+pub async fn run_api_server(port: u16, state: ApiState) -> Result<(), InclusionListBoostError> {
+    let mut router = Router::new()
+        .route("/health", get(status::handle_get_health))
+        .route("/status", get(status::handle_get_status))
+        .route("/metrics", get(metrics::handle_get_metrics))
+        .route(
+            "/eth/v1/inclusion_requests",
+            post(inclusion_request::handle_post_inclusion_request),
+        );
+
+    if state.admin_token.is_some() {
+        router = router.nest("/admin", admin_router(state.clone()));
+    } else {
+        tracing::info!("No admin token configured, admin API disabled");
+    }
+
+    let router = router.with_state(state);
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!(?addr, "Starting inclusion list status API");
+
+    axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>()).await?;
+
+    Ok(())
+}
+
+fn admin_router(state: ApiState) -> Router<ApiState> {
+    Router::new()
+        .route("/inclusion_lists/:slot", get(admin::handle_get_inclusion_list))
+        .route("/inclusion_lists/:slot/pin", post(admin::handle_pin_transaction))
+        .route("/inclusion_lists/:slot/remove", post(admin::handle_remove_transaction))
+        .route("/inclusion_lists/:slot/freeze", post(admin::handle_freeze))
+        .route("/inclusion_lists/:slot/unfreeze", post(admin::handle_unfreeze))
+        .route("/inclusion_lists/:slot/check_header", post(admin::handle_check_header))
+        .route("/delegations", get(admin::handle_get_delegations))
+        .route("/delegations/:slot/revoke", post(admin::handle_revoke_delegation))
+        .route("/protection/export", get(admin::handle_export_protection))
+        .route("/protection/import", post(admin::handle_import_protection))
+        .route_layer(middleware::from_fn_with_state(state, admin::authenticate))
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use alloy::{providers::Provider, rpc::types::SyncStatus};
+use axum::{extract::State, response::IntoResponse, Json};
+use reqwest::StatusCode;
+use serde::Serialize;
+
+use crate::{
+    inclusion_boost::{error::InclusionListBoostError, supervisor::SlotFailure},
+    status::SidecarStatus,
+};
+
+use super::ApiState;
+
+const SYNC_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum DependencyStatus {
+    Synced,
+    Syncing,
+    Unreachable { error: String },
+}
+
+#[derive(Debug, Serialize)]
+struct StatusResponse {
+    alive: bool,
+    restarts: u64,
+    slot_failures: u64,
+    last_failure: Option<SlotFailure>,
+    beacon_node: DependencyStatus,
+    execution_node: DependencyStatus,
+    #[serde(flatten)]
+    sidecar: SidecarStatus,
+}
+
+/// Returns 200 while the sidecar task is running, 503 otherwise
+pub async fn handle_get_health(State(state): State<ApiState>) -> impl IntoResponse {
+    if state.status.health.is_alive() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+pub async fn handle_get_status(State(state): State<ApiState>) -> impl IntoResponse {
+    let (beacon_node, execution_node) =
+        tokio::join!(beacon_sync_status(&state.beacon_api), execution_sync_status(&state));
+
+    let health = &state.status.health;
+    let mut sidecar = state.status.snapshot();
+
+    // The lookahead is only refreshed on epoch transitions, drop proposals that already passed
+    if let Some(head_slot) = sidecar.last_head_slot {
+        sidecar.upcoming_proposals.retain(|proposal| proposal.slot > head_slot);
+    }
+
+    Json(StatusResponse {
+        alive: health.is_alive(),
+        restarts: health.restarts(),
+        slot_failures: health.slot_failures(),
+        last_failure: health.last_failure(),
+        beacon_node,
+        execution_node,
+        sidecar,
+    })
+}
+
+async fn beacon_sync_status(beacon_url: &str) -> DependencyStatus {
+    match get_beacon_is_syncing(beacon_url).await {
+        Ok(false) => DependencyStatus::Synced,
+        Ok(true) => DependencyStatus::Syncing,
+        Err(err) => DependencyStatus::Unreachable {
+            error: format!("{err:?}"),
+        },
+    }
+}
+
+async fn get_beacon_is_syncing(beacon_url: &str) -> Result<bool, InclusionListBoostError> {
+    let url = format!("{beacon_url}/eth/v1/node/syncing");
+    let res = reqwest::Client::new()
+        .get(url)
+        .timeout(SYNC_CHECK_TIMEOUT)
+        .send()
+        .await?;
+    let json: serde_json::Value = serde_json::from_str(&res.text().await?)?;
+
+    json.pointer("/data/is_syncing")
+        .and_then(|is_syncing| is_syncing.as_bool())
+        .ok_or_else(|| "missing is_syncing in beacon node response".to_string().into())
+}
+
+async fn execution_sync_status(state: &ApiState) -> DependencyStatus {
+    match tokio::time::timeout(SYNC_CHECK_TIMEOUT, state.mempool.provider().syncing()).await {
+        Ok(Ok(SyncStatus::Info(_))) => DependencyStatus::Syncing,
+        Ok(Ok(_)) => DependencyStatus::Synced,
+        Ok(Err(err)) => DependencyStatus::Unreachable {
+            error: err.to_string(),
+        },
+        Err(_) => DependencyStatus::Unreachable {
+            error: "timed out".to_string(),
+        },
+    }
+}
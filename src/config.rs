@@ -1,8 +1,163 @@
-use serde::{Deserialize, Serialize};
+use alloy::rpc::types::beacon::BlsPublicKey;
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+use std::collections::HashSet;
+
+use crate::inclusion_boost::{
+    aggregation::{Aggregation, VantagePoint},
+    fees::BlobSchedule,
+    selection::GasBudget,
+    types::{InclusionListFormat, InclusionListMode},
+};
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct InclusionListConfig {
     pub beacon_api: String,
-    pub execution_api: String,
+    /// Execution nodes whose mempools are merged, a single url or a list of urls. The first node
+    /// that is up also serves all other execution queries
+    #[serde(deserialize_with = "one_or_many")]
+    pub execution_api: Vec<String>,
+    /// How the mempools of the `execution_api` nodes are merged, `{ threshold = 2 }` only keeps
+    /// transactions seen by at least two of them
+    #[serde(default)]
+    pub mempool_aggregation: Aggregation,
     pub relay: String,
+    /// Port of the module's status API
+    #[serde(default = "default_status_port")]
+    pub status_port: u16,
+    /// Bearer token required by the admin API, which is disabled when unset
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// Inclusion requests accepted per client ip per minute
+    #[serde(default = "default_inclusion_requests_per_minute")]
+    pub inclusion_requests_per_minute: u32,
+    /// Maximum number of queued inclusion requests
+    #[serde(default = "default_inclusion_request_queue_size")]
+    pub inclusion_request_queue_size: usize,
+    /// TOML file with the inclusion policy, every candidate is accepted when unset
+    #[serde(default)]
+    pub policy_file: Option<String>,
+    /// Simulate candidates against the head state and drop the ones that fail
+    #[serde(default = "default_simulate_candidates")]
+    pub simulate_candidates: bool,
+    /// Require fee caps to cover the next block's base fees after this many more full blocks
+    #[serde(default)]
+    pub base_fee_headroom_slots: u64,
+    /// Execution gas the inclusion list may claim, defaults to the gas left in the parent block
+    #[serde(default)]
+    pub gas_budget: GasBudget,
+    /// Blob target, limit and base fee update fraction by fork activation timestamp,
+    /// defaults to mainnet's Cancun and Prague values
+    #[serde(default)]
+    pub blob_schedule: BlobSchedule,
+    /// Number of recent blocks pending transactions are checked against for censorship
+    #[serde(default = "default_censorship_window_blocks")]
+    pub censorship_window_blocks: usize,
+    /// File with one sanctioned address per line, candidates touching them are prioritized
+    #[serde(default)]
+    pub sanctions_file: Option<String>,
+    /// Also tag candidates reaching a sanctioned address through internal calls, using `debug_traceCall`
+    #[serde(default)]
+    pub trace_sanctioned_calls: bool,
+    /// Publish spot lists for our own slots, forward lists for the slot after them, or both
+    #[serde(default)]
+    pub inclusion_list_mode: InclusionListMode,
+    /// Validators not run by this module that opted in to forward lists targeting their slots
+    #[serde(default)]
+    pub forward_opted_in_validators: HashSet<usize>,
+    /// Validators whose lists are conditional, satisfied by a full block as well
+    #[serde(default)]
+    pub conditional_validators: HashSet<usize>,
+    /// Publish constraints lists or EIP-7805 lists carrying whole transactions
+    #[serde(default)]
+    pub inclusion_list_format: InclusionListFormat,
+    /// Other execution nodes that each build a list from their own mempool next to `execution_api`
+    #[serde(default)]
+    pub vantage_points: Vec<VantagePoint>,
+    /// How the lists of `execution_api` and the vantage points are merged
+    #[serde(default)]
+    pub aggregation: Aggregation,
+    /// Delegate inclusion list authority for our upcoming slots, existing delegations are
+    /// revoked when turned off
+    #[serde(default = "default_delegate_inclusion_lists")]
+    pub delegate_inclusion_lists: bool,
+    /// JSON file the sent delegations are kept in across restarts
+    #[serde(default)]
+    pub delegation_state_file: Option<String>,
+    /// Proxy key of the signer that inclusion list authority is delegated to and that signs the
+    /// lists of delegated slots, the validator keys only sign the delegations
+    #[serde(default)]
+    pub delegate_pubkey: Option<BlsPublicKey>,
+    /// Sign inclusion lists with a proxy key per validator, generated by the signer at startup
+    #[serde(default)]
+    pub use_proxy_keys: bool,
+    /// JSON file the proxy delegations are kept in, so proxy keys are reused across restarts.
+    /// Required with `use_proxy_keys`, the signer can't hand out delegations of existing keys
+    #[serde(default)]
+    pub proxy_delegations_file: Option<String>,
+    /// Rebuild and resubmit changed lists this often during the slot, revisions are off when 0
+    #[serde(default)]
+    pub revision_interval_ms: u64,
+    /// Stop revising this long before the start of the slot, when the proposer calls `get_header`
+    #[serde(default = "default_revision_cutoff_ms")]
+    pub revision_cutoff_ms: u64,
+    /// Build the lists this far into the slot before the proposal, on the slot clock, instead of
+    /// on the head event of that slot
+    #[serde(default)]
+    pub build_offset_ms: Option<u64>,
+    /// Lists are not submitted later than this long before the proposer calls `get_header` at the
+    /// start of its slot, 0 disables the deadline
+    #[serde(default)]
+    pub submission_deadline_ms: u64,
+    /// JSON file of every signed inclusion list and delegation root, in the interchange format
+    /// served by the admin API. Conflicting messages are never signed
+    #[serde(default)]
+    pub protection_db_file: Option<String>,
+}
+
+fn default_status_port() -> u16 {
+    18551
+}
+
+fn default_inclusion_requests_per_minute() -> u32 {
+    10
+}
+
+fn default_inclusion_request_queue_size() -> usize {
+    256
+}
+
+fn default_simulate_candidates() -> bool {
+    true
+}
+
+fn default_censorship_window_blocks() -> usize {
+    8
+}
+
+fn default_delegate_inclusion_lists() -> bool {
+    true
+}
+
+fn default_revision_cutoff_ms() -> u64 {
+    1000
+}
+
+/// Accept a single url as well as a list of urls
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let urls = match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(url) => vec![url],
+        OneOrMany::Many(urls) => urls,
+    };
+    if urls.is_empty() {
+        return Err(D::Error::custom("execution_api needs at least one url"));
+    }
+    Ok(urls)
 }
@@ -0,0 +1,61 @@
+use std::collections::{BTreeMap, HashMap};
+
+use alloy::primitives::B256;
+use serde::{Deserialize, Serialize};
+
+/// Name of the vantage point backed by `execution_api`
+pub const LOCAL_VANTAGE_POINT: &str = "local";
+
+/// Another execution node whose mempool view contributes to our lists
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VantagePoint {
+    pub name: String,
+    pub execution_api: String,
+}
+
+/// How the lists of the vantage points are merged into the published list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregation {
+    /// Every transaction listed by at least one vantage point
+    #[default]
+    Union,
+    /// Transactions listed by at least this many vantage points
+    Threshold(usize),
+}
+
+impl Aggregation {
+    pub fn required_votes(&self) -> usize {
+        match self {
+            Aggregation::Union => 1,
+            Aggregation::Threshold(votes) => (*votes).max(1),
+        }
+    }
+}
+
+/// The lists built from each vantage point for a slot, merged like the lists of an
+/// inclusion list committee
+#[derive(Debug, Default)]
+pub struct Committee {
+    votes: BTreeMap<B256, Vec<String>>,
+}
+
+impl Committee {
+    /// Record the transactions `source` would list
+    pub fn vote(&mut self, source: &str, tx_hashes: impl IntoIterator<Item = B256>) {
+        for tx_hash in tx_hashes {
+            let sources = self.votes.entry(tx_hash).or_default();
+            if !sources.iter().any(|s| s == source) {
+                sources.push(source.to_string());
+            }
+        }
+    }
+
+    /// Transactions listed by enough vantage points, with the vantage points that listed them
+    pub fn aggregate(self, aggregation: Aggregation) -> HashMap<B256, Vec<String>> {
+        self.votes
+            .into_iter()
+            .filter(|(_, sources)| sources.len() >= aggregation.required_votes())
+            .collect()
+    }
+}
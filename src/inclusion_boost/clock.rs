@@ -0,0 +1,124 @@
+use std::str::FromStr;
+
+use alloy::primitives::B256;
+
+use super::error::InclusionListBoostError;
+
+/// Slot duration on mainnet and the public testnets, used when the beacon spec doesn't say
+pub const SECONDS_PER_SLOT: u64 = 12;
+
+/// Epoch length on mainnet and the public testnets, used when the beacon spec doesn't say
+pub const SLOTS_PER_EPOCH: u64 = 32;
+
+/// The beacon node's genesis, fetched once at startup
+#[derive(Debug, Clone, Copy)]
+pub struct Genesis {
+    pub genesis_time: u64,
+    pub genesis_validators_root: B256,
+}
+
+impl Genesis {
+    pub async fn fetch(client: &reqwest::Client, beacon_url: &str) -> Result<Self, InclusionListBoostError> {
+        let res = client.get(format!("{beacon_url}/eth/v1/beacon/genesis")).send().await?;
+        let genesis: serde_json::Value = serde_json::from_str(&res.text().await?)?;
+
+        let genesis_time = genesis
+            .pointer("/data/genesis_time")
+            .and_then(|time| time.as_str())
+            .and_then(|time| time.parse().ok())
+            .ok_or_else(|| "missing genesis_time in beacon node response".to_string())?;
+
+        let genesis_validators_root = genesis
+            .pointer("/data/genesis_validators_root")
+            .and_then(|root| root.as_str())
+            .and_then(|root| B256::from_str(root).ok())
+            .ok_or_else(|| "missing genesis_validators_root in beacon node response".to_string())?;
+
+        Ok(Self {
+            genesis_time,
+            genesis_validators_root,
+        })
+    }
+}
+
+/// Maps slots to wall clock time
+#[derive(Debug, Clone, Copy)]
+pub struct SlotClock {
+    genesis_time: u64,
+    seconds_per_slot: u64,
+    slots_per_epoch: u64,
+}
+
+impl SlotClock {
+    pub fn new(genesis_time: u64, seconds_per_slot: u64, slots_per_epoch: u64) -> Self {
+        Self {
+            genesis_time,
+            seconds_per_slot,
+            slots_per_epoch,
+        }
+    }
+
+    /// Clock of the beacon node's chain, from its genesis time and spec
+    pub async fn fetch(
+        client: &reqwest::Client,
+        beacon_url: &str,
+        genesis: &Genesis,
+    ) -> Result<Self, InclusionListBoostError> {
+        let res = client.get(format!("{beacon_url}/eth/v1/config/spec")).send().await?;
+        let spec: serde_json::Value = serde_json::from_str(&res.text().await?)?;
+
+        let genesis_time = genesis.genesis_time;
+        let seconds_per_slot = spec
+            .pointer("/data/SECONDS_PER_SLOT")
+            .and_then(|seconds| seconds.as_str())
+            .and_then(|seconds| seconds.parse().ok())
+            .unwrap_or_else(|| {
+                tracing::warn!(SECONDS_PER_SLOT, "Missing SECONDS_PER_SLOT in beacon spec, using the default");
+                SECONDS_PER_SLOT
+            });
+        let slots_per_epoch = spec
+            .pointer("/data/SLOTS_PER_EPOCH")
+            .and_then(|slots| slots.as_str())
+            .and_then(|slots| slots.parse().ok())
+            .unwrap_or_else(|| {
+                tracing::warn!(SLOTS_PER_EPOCH, "Missing SLOTS_PER_EPOCH in beacon spec, using the default");
+                SLOTS_PER_EPOCH
+            });
+
+        tracing::info!(genesis_time, seconds_per_slot, slots_per_epoch, "Initialized slot clock");
+        Ok(Self::new(genesis_time, seconds_per_slot, slots_per_epoch))
+    }
+
+    pub fn seconds_per_slot(&self) -> u64 {
+        self.seconds_per_slot
+    }
+
+    pub fn slots_per_epoch(&self) -> u64 {
+        self.slots_per_epoch
+    }
+
+    /// Epoch that `slot` belongs to
+    pub fn epoch(&self, slot: u64) -> u64 {
+        slot / self.slots_per_epoch
+    }
+
+    /// Unix time in milliseconds at which `slot` starts
+    pub fn slot_start_millis(&self, slot: u64) -> u64 {
+        (self.genesis_time + slot * self.seconds_per_slot) * 1000
+    }
+
+    /// Slot in progress at `now_millis`
+    pub fn slot_at(&self, now_millis: u64) -> u64 {
+        now_millis.saturating_sub(self.genesis_time * 1000) / (self.seconds_per_slot * 1000)
+    }
+
+    /// The first slot reaching `offset_ms` into it after `now_millis`, and the unix time in
+    /// milliseconds at which it does
+    pub fn next_slot_offset(&self, now_millis: u64, offset_ms: u64) -> (u64, u64) {
+        let mut slot = self.slot_at(now_millis);
+        while self.slot_start_millis(slot) + offset_ms <= now_millis {
+            slot += 1;
+        }
+        (slot, self.slot_start_millis(slot) + offset_ms)
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use alloy::rpc::types::beacon::BlsPublicKey;
+use serde::{Deserialize, Serialize};
+
+use super::{error::InclusionListBoostError, util::write_json};
+
+/// Inclusion list authority for a slot, delegated by its proposer at a relay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delegation {
+    pub slot: u64,
+    pub validator_index: usize,
+    /// Proposer key signing the delegation
+    pub validator_pubkey: BlsPublicKey,
+    /// Key the authority is delegated to
+    pub delegate_pubkey: BlsPublicKey,
+    pub relay: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DelegationState {
+    delegations: Vec<Delegation>,
+    pending_revocations: Vec<Delegation>,
+    revoked_slots: BTreeSet<u64>,
+}
+
+/// Tracks the delegations sent to relays so they are sent once, revoked when they no longer
+/// match the configuration, and survive restarts when a state file is configured
+#[derive(Debug, Default)]
+pub struct DelegationManager {
+    path: Option<String>,
+    /// Delegations the relays accepted, keyed by slot and relay
+    delegations: BTreeMap<(u64, String), Delegation>,
+    /// Delegations to withdraw, kept until the relay accepts the revocation
+    pending_revocations: Vec<Delegation>,
+    /// Slots the operator revoked, never delegated again
+    revoked_slots: BTreeSet<u64>,
+}
+
+impl DelegationManager {
+    /// Start from the state saved at `path`, or from scratch if there is none yet
+    pub fn load(path: Option<String>) -> Result<Self, InclusionListBoostError> {
+        let mut manager = Self {
+            path,
+            ..Default::default()
+        };
+
+        let Some(path) = manager.path.as_deref().filter(|path| Path::new(path).exists()) else {
+            return Ok(manager);
+        };
+
+        let state: DelegationState = serde_json::from_str(&fs::read_to_string(path)?)?;
+        tracing::info!(
+            path,
+            delegations = state.delegations.len(),
+            pending_revocations = state.pending_revocations.len(),
+            "Loaded delegation state"
+        );
+
+        manager.delegations = state
+            .delegations
+            .into_iter()
+            .map(|delegation| ((delegation.slot, delegation.relay.clone()), delegation))
+            .collect();
+        manager.pending_revocations = state.pending_revocations;
+        manager.revoked_slots = state.revoked_slots;
+
+        Ok(manager)
+    }
+
+    pub fn delegations(&self) -> Vec<Delegation> {
+        self.delegations.values().cloned().collect()
+    }
+
+    /// Key the authority of `slot` at `relay` was delegated to
+    pub fn delegate_of(&self, slot: u64, relay: &str) -> Option<BlsPublicKey> {
+        self.delegations.get(&(slot, relay.to_string())).map(|delegation| delegation.delegate_pubkey)
+    }
+
+    pub fn pending_revocations(&self) -> Vec<Delegation> {
+        self.pending_revocations.clone()
+    }
+
+    /// Compare the delegations the configuration asks for from `from_slot` on with the ones
+    /// already sent. Returns the ones to send, sent ones that no longer match are queued for
+    /// revocation. A changed delegate key is revoked and delegated again
+    pub fn plan(&mut self, desired: Vec<Delegation>, from_slot: u64) -> Vec<Delegation> {
+        let desired = desired
+            .into_iter()
+            .filter(|delegation| !self.revoked_slots.contains(&delegation.slot))
+            .map(|delegation| ((delegation.slot, delegation.relay.clone()), delegation))
+            .collect::<BTreeMap<_, _>>();
+
+        let stale = self
+            .delegations
+            .iter()
+            .filter(|(key, delegation)| delegation.slot >= from_slot && desired.get(*key) != Some(delegation))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in stale {
+            if let Some(delegation) = self.delegations.remove(&key) {
+                tracing::info!(slot = delegation.slot, relay = delegation.relay, "Delegation no longer configured");
+                self.pending_revocations.push(delegation);
+            }
+        }
+        self.persist();
+
+        desired
+            .into_iter()
+            .filter(|(key, _)| !self.delegations.contains_key(key))
+            .map(|(_, delegation)| delegation)
+            .collect()
+    }
+
+    pub fn record_delegated(&mut self, delegation: Delegation) {
+        self.delegations.insert((delegation.slot, delegation.relay.clone()), delegation);
+        self.persist();
+    }
+
+    pub fn record_revoked(&mut self, delegation: &Delegation) {
+        self.pending_revocations.retain(|pending| pending != delegation);
+        self.persist();
+    }
+
+    /// Operator command: withdraw every delegation of `slot` and never delegate it again
+    pub fn revoke_slot(&mut self, slot: u64) {
+        self.revoked_slots.insert(slot);
+
+        let revoked = self.delegations.keys().filter(|(s, _)| *s == slot).cloned().collect::<Vec<_>>();
+        for key in revoked {
+            if let Some(delegation) = self.delegations.remove(&key) {
+                self.pending_revocations.push(delegation);
+            }
+        }
+        self.persist();
+    }
+
+    /// Forget delegations and revocations of slots before `slot`
+    pub fn prune(&mut self, slot: u64) {
+        let before = self.delegations.len() + self.pending_revocations.len() + self.revoked_slots.len();
+
+        self.delegations.retain(|(s, _), _| *s >= slot);
+        self.pending_revocations.retain(|delegation| delegation.slot >= slot);
+        self.revoked_slots.retain(|s| *s >= slot);
+
+        if self.delegations.len() + self.pending_revocations.len() + self.revoked_slots.len() != before {
+            self.persist();
+        }
+    }
+
+    /// Write the state to the state file, failures are logged and retried on the next change
+    fn persist(&self) {
+        let Some(path) = self.path.as_deref() else {
+            return;
+        };
+
+        let state = DelegationState {
+            delegations: self.delegations(),
+            pending_revocations: self.pending_revocations.clone(),
+            revoked_slots: self.revoked_slots.clone(),
+        };
+
+        if let Err(err) = write_json(path, &state) {
+            tracing::error!(?err, path, "Failed to persist delegation state");
+        }
+    }
+}
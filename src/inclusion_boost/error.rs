@@ -17,6 +17,11 @@ pub enum InclusionListBoostError {
     LookaheadError(LookaheadError),
     ParseIntError(ParseIntError),
     Serde(serde_json::Error),
+    Io(std::io::Error),
+    Eip2718(alloy::eips::eip2718::Eip2718Error),
+    Signature(alloy::primitives::SignatureError),
+    /// Signing was refused by the protection database
+    ConflictingSignature(String),
 }
 
 impl From<String> for InclusionListBoostError {
@@ -74,6 +79,21 @@ impl From<serde_json::Error> for InclusionListBoostError {
     }
 }
 
+impl From<std::io::Error> for InclusionListBoostError {
+    fn from(value: std::io::Error) -> Self {
+        InclusionListBoostError::Io(value)
+    }
+}
+impl From<alloy::eips::eip2718::Eip2718Error> for InclusionListBoostError {
+    fn from(value: alloy::eips::eip2718::Eip2718Error) -> Self {
+        InclusionListBoostError::Eip2718(value)
+    }
+}
+impl From<alloy::primitives::SignatureError> for InclusionListBoostError {
+    fn from(value: alloy::primitives::SignatureError) -> Self {
+        InclusionListBoostError::Signature(value)
+    }
+}
 
 // impl From<Utf8Error> for InclusionListBoostError {
 //     fn from(value: Utf8Error) -> Self {
@@ -0,0 +1,191 @@
+use alloy::rpc::types::Header;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    types::{DropReason, DroppedCandidate, Transaction},
+};
+
+/// EIP-1559 parameters
+const ELASTICITY_MULTIPLIER: u128 = 2;
+const BASE_FEE_MAX_CHANGE_DENOMINATOR: u128 = 8;
+
+/// EIP-4844 parameters
+pub const DATA_GAS_PER_BLOB: u128 = 131_072;
+const MIN_BLOB_BASE_FEE: u128 = 1;
+
+/// Blob parameters of a fork, in effect for blocks with a timestamp at or after `timestamp`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobParams {
+    pub timestamp: u64,
+    pub target: u128,
+    pub max: u128,
+    pub base_fee_update_fraction: u128,
+}
+
+impl BlobParams {
+    /// Mainnet Cancun, EIP-4844
+    pub const CANCUN: Self = Self {
+        timestamp: 1_710_338_135,
+        target: 3,
+        max: 6,
+        base_fee_update_fraction: 3_338_477,
+    };
+    /// Mainnet Prague, EIP-7691
+    pub const PRAGUE: Self = Self {
+        timestamp: 1_746_612_311,
+        target: 6,
+        max: 9,
+        base_fee_update_fraction: 5_007_716,
+    };
+
+    pub fn max_blob_gas(&self) -> u128 {
+        self.max * DATA_GAS_PER_BLOB
+    }
+
+    /// Excess blob gas of a block following a block with the given excess and blob gas usage
+    pub fn next_excess_blob_gas(&self, excess_blob_gas: u128, blob_gas_used: u128) -> u128 {
+        (excess_blob_gas + blob_gas_used).saturating_sub(self.target * DATA_GAS_PER_BLOB)
+    }
+
+    /// Blob base fee of a block with `excess_blob_gas`
+    pub fn blob_base_fee(&self, excess_blob_gas: u128) -> u128 {
+        fake_exponential(MIN_BLOB_BASE_FEE, excess_blob_gas, self.base_fee_update_fraction)
+    }
+}
+
+/// Blob parameters by fork, like the EIP-7840 `blobSchedule` of execution clients.
+/// Defaults to mainnet's, other networks have to configure theirs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BlobSchedule(pub Vec<BlobParams>);
+
+impl Default for BlobSchedule {
+    fn default() -> Self {
+        Self(vec![BlobParams::CANCUN, BlobParams::PRAGUE])
+    }
+}
+
+impl BlobSchedule {
+    /// Parameters of the latest fork active at `timestamp`, or of the earliest fork before any
+    pub fn at(&self, timestamp: u64) -> BlobParams {
+        self.0
+            .iter()
+            .filter(|params| params.timestamp <= timestamp)
+            .max_by_key(|params| params.timestamp)
+            .or_else(|| self.0.iter().min_by_key(|params| params.timestamp))
+            .copied()
+            .unwrap_or(BlobParams::PRAGUE)
+    }
+
+    /// Parameters of the block following `parent`, one slot of `seconds_per_slot` later
+    pub fn after(&self, parent: &Header, seconds_per_slot: u64) -> BlobParams {
+        self.at(parent.timestamp + seconds_per_slot)
+    }
+}
+
+/// Base fee of the block following a block with the given gas usage, limit and base fee
+pub fn next_base_fee(gas_used: u128, gas_limit: u128, base_fee: u128) -> u128 {
+    let gas_target = gas_limit / ELASTICITY_MULTIPLIER;
+    if gas_target == 0 || gas_used == gas_target {
+        return base_fee;
+    }
+
+    if gas_used > gas_target {
+        let delta = (base_fee * (gas_used - gas_target) / gas_target / BASE_FEE_MAX_CHANGE_DENOMINATOR).max(1);
+        base_fee + delta
+    } else {
+        let delta = base_fee * (gas_target - gas_used) / gas_target / BASE_FEE_MAX_CHANGE_DENOMINATOR;
+        base_fee.saturating_sub(delta)
+    }
+}
+
+/// Integer approximation of `factor * e ** (numerator / denominator)` from EIP-4844
+fn fake_exponential(factor: u128, numerator: u128, denominator: u128) -> u128 {
+    let mut i = 1;
+    let mut output = 0;
+    let mut numerator_accum = factor * denominator;
+
+    while numerator_accum > 0 {
+        output += numerator_accum;
+        numerator_accum = numerator_accum.saturating_mul(numerator) / (denominator * i);
+        i += 1;
+    }
+
+    output / denominator
+}
+
+/// Fees a transaction has to cover to be included in the next block
+#[derive(Debug, Clone, PartialEq)]
+pub struct NextBlockFees {
+    pub base_fee: u128,
+    /// `None` before the parent block carries blob gas fields
+    pub blob_base_fee: Option<u128>,
+}
+
+impl NextBlockFees {
+    /// Compute the fees of the block after `parent`, which has `blob_params`. With `headroom_slots`
+    /// the fees are raised to the worst case after that many additional full blocks, for lists
+    /// that span several slots
+    pub fn from_parent(parent: &Header, headroom_slots: u64, blob_params: BlobParams) -> Self {
+        let base_fee = parent
+            .base_fee_per_gas
+            .map(|base_fee| next_base_fee(parent.gas_used, parent.gas_limit, base_fee))
+            .unwrap_or_default();
+
+        let blob_base_fee = parent
+            .excess_blob_gas
+            .zip(parent.blob_gas_used)
+            .map(|(excess, used)| blob_params.blob_base_fee(blob_params.next_excess_blob_gas(excess, used)));
+
+        Self {
+            base_fee: with_headroom(base_fee, headroom_slots),
+            blob_base_fee: blob_base_fee.map(|fee| with_headroom(fee, headroom_slots)),
+        }
+    }
+
+    pub fn check(&self, tx: &Transaction) -> Result<(), DropReason> {
+        let max_fee_per_gas = tx.max_fee_per_gas.unwrap_or_default();
+        if max_fee_per_gas < self.base_fee {
+            return Err(DropReason::BaseFeeNotCovered {
+                max_fee_per_gas,
+                base_fee: self.base_fee,
+            });
+        }
+
+        if let (Some(blob_base_fee), Some(max_fee_per_blob_gas)) =
+            (self.blob_base_fee, tx.max_fee_per_blob_gas)
+        {
+            if max_fee_per_blob_gas < blob_base_fee {
+                return Err(DropReason::BlobBaseFeeNotCovered {
+                    max_fee_per_blob_gas,
+                    blob_base_fee,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Split `transactions` into the ones whose fee caps cover the next block's fees and the rest
+    pub fn filter(&self, transactions: Vec<Transaction>) -> (Vec<Transaction>, Vec<DroppedCandidate>) {
+        let mut accepted = vec![];
+        let mut dropped = vec![];
+
+        for tx in transactions {
+            match self.check(&tx) {
+                Ok(()) => accepted.push(tx),
+                Err(reason) => dropped.push(DroppedCandidate {
+                    tx_hash: tx.tx_hash,
+                    reason,
+                }),
+            }
+        }
+
+        (accepted, dropped)
+    }
+}
+
+/// Raise `fee` by the maximum per block increase of 12.5%, `slots` times
+fn with_headroom(fee: u128, slots: u64) -> u128 {
+    (0..slots).fold(fee, |fee, _| fee.saturating_add(fee.div_ceil(BASE_FEE_MAX_CHANGE_DENOMINATOR)))
+}
@@ -0,0 +1,203 @@
+use std::str::FromStr;
+
+use alloy::{
+    hex,
+    primitives::{Bytes, B256},
+    rpc::types::beacon::BlsSignature,
+};
+use serde::{Deserialize, Serialize};
+use ssz_types::{
+    typenum::{U1048576, U1073741824, U32, U4},
+    FixedVector, VariableList,
+};
+use tree_hash::TreeHash;
+use tree_hash_derive::TreeHash;
+
+use super::{
+    clock::{Genesis, SlotClock},
+    error::InclusionListBoostError,
+};
+
+/// EIP-7805 signing domain type of inclusion lists
+pub const DOMAIN_INCLUSION_LIST_COMMITTEE: [u8; 4] = [0x0c, 0x00, 0x00, 0x00];
+/// EIP-7805 limit on the total size of the transactions in a list
+pub const MAX_BYTES_PER_INCLUSION_LIST: usize = 8192;
+
+type MaxBytesPerTransaction = U1073741824;
+type MaxTransactionsPerPayload = U1048576;
+
+pub type FocilTransaction = VariableList<u8, MaxBytesPerTransaction>;
+
+/// EIP-7805 `InclusionList` container
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, TreeHash)]
+pub struct FocilInclusionList {
+    #[serde(with = "serde_utils::quoted_u64")]
+    pub slot: u64,
+    #[serde(with = "serde_utils::quoted_u64")]
+    pub validator_index: u64,
+    #[serde(with = "ssz_types::serde_utils::hex_fixed_vec")]
+    pub inclusion_list_committee_root: FixedVector<u8, U32>,
+    #[serde(with = "ssz_types::serde_utils::list_of_hex_var_list")]
+    pub transactions: VariableList<FocilTransaction, MaxTransactionsPerPayload>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedFocilInclusionList {
+    pub message: FocilInclusionList,
+    pub signature: BlsSignature,
+}
+
+#[derive(TreeHash)]
+struct ForkData {
+    current_version: FixedVector<u8, U4>,
+    genesis_validators_root: [u8; 32],
+}
+
+#[derive(TreeHash)]
+struct SigningData {
+    object_root: [u8; 32],
+    domain: [u8; 32],
+}
+
+impl FocilInclusionList {
+    /// Build a list from EIP-2718 encoded transactions, in order, leaving out the ones that
+    /// would push the list over `MAX_BYTES_PER_INCLUSION_LIST`
+    pub fn new(slot: u64, validator_index: u64, inclusion_list_committee_root: B256, raw_transactions: &[Bytes]) -> Self {
+        let mut size = 0;
+        let mut transactions = vec![];
+
+        for raw in raw_transactions {
+            if size + raw.len() > MAX_BYTES_PER_INCLUSION_LIST {
+                continue;
+            }
+            size += raw.len();
+            transactions.push(FocilTransaction::from(raw.to_vec()));
+        }
+
+        Self {
+            slot,
+            validator_index,
+            inclusion_list_committee_root: FixedVector::from(inclusion_list_committee_root.to_vec()),
+            transactions: VariableList::from(transactions),
+        }
+    }
+
+    /// Root to sign, the list's root mixed with the inclusion list committee domain
+    pub fn signing_root(&self, domain: [u8; 32]) -> B256 {
+        SigningData {
+            object_root: self.tree_hash_root().into(),
+            domain,
+        }
+        .tree_hash_root()
+    }
+}
+
+/// `compute_domain` of the consensus specs for `DOMAIN_INCLUSION_LIST_COMMITTEE`
+pub fn compute_domain(fork_version: [u8; 4], genesis_validators_root: B256) -> [u8; 32] {
+    let fork_data_root = ForkData {
+        current_version: FixedVector::from(fork_version.to_vec()),
+        genesis_validators_root: genesis_validators_root.into(),
+    }
+    .tree_hash_root();
+
+    let mut domain = [0u8; 32];
+    domain[..4].copy_from_slice(&DOMAIN_INCLUSION_LIST_COMMITTEE);
+    domain[4..].copy_from_slice(&fork_data_root[..28]);
+    domain
+}
+
+/// What the signing domain is computed from, fetched once at startup
+#[derive(Debug, Clone)]
+pub struct DomainInputs {
+    genesis_validators_root: B256,
+    /// Fork versions by activation epoch
+    fork_versions: Vec<(u64, [u8; 4])>,
+    slots_per_epoch: u64,
+}
+
+impl DomainInputs {
+    pub fn new(genesis_validators_root: B256, fork_versions: Vec<(u64, [u8; 4])>, slots_per_epoch: u64) -> Self {
+        Self {
+            genesis_validators_root,
+            fork_versions,
+            slots_per_epoch,
+        }
+    }
+
+    /// The beacon node's fork schedule, including forks that are yet to activate
+    pub async fn fetch(
+        client: &reqwest::Client,
+        beacon_url: &str,
+        genesis: &Genesis,
+        slot_clock: &SlotClock,
+    ) -> Result<Self, InclusionListBoostError> {
+        let res = client.get(format!("{beacon_url}/eth/v1/config/fork_schedule")).send().await?;
+        let schedule: serde_json::Value = serde_json::from_str(&res.text().await?)?;
+
+        let fork_versions = schedule
+            .pointer("/data")
+            .and_then(|forks| forks.as_array())
+            .map(|forks| {
+                forks
+                    .iter()
+                    .filter_map(|fork| {
+                        let epoch = fork.get("epoch")?.as_str()?.parse().ok()?;
+                        let version = hex::decode(fork.get("current_version")?.as_str()?).ok()?;
+                        Some((epoch, version.try_into().ok()?))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|forks| !forks.is_empty())
+            .ok_or_else(|| "missing fork schedule in beacon node response".to_string())?;
+
+        Ok(Self::new(genesis.genesis_validators_root, fork_versions, slot_clock.slots_per_epoch()))
+    }
+
+    /// Epoch that `slot` belongs to
+    pub fn epoch(&self, slot: u64) -> u64 {
+        slot / self.slots_per_epoch
+    }
+
+    /// Signing domain of lists for `slot`, under the fork of its epoch
+    pub fn domain(&self, slot: u64) -> [u8; 32] {
+        let epoch = self.epoch(slot);
+        let fork_version = self
+            .fork_versions
+            .iter()
+            .filter(|(activation_epoch, _)| *activation_epoch <= epoch)
+            .max_by_key(|(activation_epoch, _)| *activation_epoch)
+            .map(|(_, version)| *version)
+            .unwrap_or_default();
+
+        compute_domain(fork_version, self.genesis_validators_root)
+    }
+}
+
+/// Inclusion list committee root for `slot` of `epoch`, from beacon nodes that serve EIP-7805 duties
+pub async fn fetch_committee_root(
+    client: &reqwest::Client,
+    beacon_url: &str,
+    epoch: u64,
+    slot: u64,
+    validator_index: u64,
+) -> Result<Option<B256>, InclusionListBoostError> {
+    let url = format!("{beacon_url}/eth/v1/validator/duties/inclusion_list/{epoch}");
+    let res = client.post(url).json(&[validator_index.to_string()]).send().await?;
+    if !res.status().is_success() {
+        return Ok(None);
+    }
+
+    let json: serde_json::Value = serde_json::from_str(&res.text().await?)?;
+    let slot = slot.to_string();
+    let root = json
+        .pointer("/data")
+        .and_then(|duties| duties.as_array())
+        .and_then(|duties| {
+            duties.iter().find(|duty| duty.get("slot").and_then(|s| s.as_str()) == Some(slot.as_str()))
+        })
+        .and_then(|duty| duty.get("inclusion_list_committee_root"))
+        .and_then(|root| root.as_str())
+        .and_then(|root| B256::from_str(root).ok());
+
+    Ok(root)
+}
@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+
+use alloy::{primitives::Address, rpc::types::Block};
+use serde::Serialize;
+
+use super::{selection::effective_tip, types::Transaction};
+
+/// What the censorship score needs to know about a recent block
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BlockRecord {
+    pub number: u64,
+    pub timestamp: u64,
+    pub base_fee: u128,
+    pub gas_limit: u128,
+    pub gas_used: u128,
+    /// Lowest tip per gas paid by a transaction in the block, `None` for empty blocks
+    pub lowest_included_tip: Option<u128>,
+    pub fee_recipient: Address,
+    pub extra_data: String,
+    /// Builder that won the block according to the relay's data API, if it delivered the payload
+    pub builder_pubkey: Option<String>,
+    /// Pending transactions that could have fit into the block but were left out
+    pub skipped_transactions: u64,
+}
+
+impl BlockRecord {
+    pub fn from_block(block: &Block) -> Self {
+        let base_fee = block.header.base_fee_per_gas.unwrap_or_default();
+        let lowest_included_tip = block
+            .transactions
+            .txns()
+            .map(|tx| effective_tip(&Transaction::from(tx.clone()), base_fee))
+            .min();
+
+        Self {
+            number: block.header.number.unwrap_or_default(),
+            timestamp: block.header.timestamp,
+            base_fee,
+            gas_limit: block.header.gas_limit,
+            gas_used: block.header.gas_used,
+            lowest_included_tip,
+            fee_recipient: block.header.miner,
+            extra_data: String::from_utf8_lossy(&block.header.extra_data).trim_matches('\0').to_string(),
+            builder_pubkey: None,
+            skipped_transactions: 0,
+        }
+    }
+
+    /// The relay's builder pubkey when known, the fee recipient otherwise
+    pub fn builder(&self) -> String {
+        self.builder_pubkey.clone().unwrap_or_else(|| self.fee_recipient.to_string())
+    }
+
+    /// Whether `tx` fit into the block's unused gas while paying at least the lowest tip the
+    /// builder accepted. A transaction first seen after the block's timestamp never fits
+    pub fn could_have_fit(&self, tx: &Transaction, first_seen: Option<u64>) -> bool {
+        if first_seen.is_some_and(|seen| seen > self.timestamp) {
+            return false;
+        }
+
+        let tip = effective_tip(tx, self.base_fee);
+        tx.gas <= self.gas_limit.saturating_sub(self.gas_used) &&
+            tip > 0 &&
+            self.lowest_included_tip.map_or(true, |lowest| tip >= lowest)
+    }
+}
+
+/// Rolling window of the most recent blocks
+#[derive(Debug)]
+pub struct BlockHistory {
+    window: usize,
+    blocks: BTreeMap<u64, BlockRecord>,
+}
+
+impl BlockHistory {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            blocks: BTreeMap::new(),
+        }
+    }
+
+    /// Add `record` and forget blocks that fell out of the window behind the newest block
+    pub fn insert(&mut self, record: BlockRecord) {
+        self.blocks.insert(record.number, record);
+
+        let newest = self.blocks.keys().next_back().copied().unwrap_or_default();
+        let oldest = newest.saturating_sub(self.window as u64 - 1);
+        self.blocks.retain(|number, _| *number >= oldest);
+    }
+
+    /// Block numbers in the window ending at `latest` that haven't been recorded yet
+    pub fn missing(&self, latest: u64) -> Vec<u64> {
+        let oldest = latest.saturating_sub(self.window as u64 - 1);
+        (oldest..latest).filter(|number| !self.blocks.contains_key(number)).collect()
+    }
+
+    /// Attribute a recorded block to the builder whose payload the relay delivered for it
+    pub fn set_builder(&mut self, block_number: u64, builder_pubkey: String) {
+        if let Some(block) = self.blocks.get_mut(&block_number) {
+            block.builder_pubkey = Some(builder_pubkey);
+        }
+    }
+
+    pub fn contains(&self, block_number: u64) -> bool {
+        self.blocks.contains_key(&block_number)
+    }
+
+    /// Number of blocks in the window `tx` could have been included in but wasn't
+    pub fn blocks_skipped(&self, tx: &Transaction, first_seen: Option<u64>) -> u64 {
+        self.blocks.values().filter(|block| block.could_have_fit(tx, first_seen)).count() as u64
+    }
+
+    pub fn records(&self) -> Vec<BlockRecord> {
+        self.blocks.values().cloned().collect()
+    }
+
+    /// Aggregate the blocks in the window by builder, most skipped transactions first
+    pub fn builder_report(&self) -> Vec<BuilderSkipReport> {
+        let mut builders: BTreeMap<String, BuilderSkipReport> = BTreeMap::new();
+
+        for block in self.blocks.values() {
+            let report = builders.entry(block.builder()).or_insert_with(|| BuilderSkipReport {
+                builder: block.builder(),
+                fee_recipient: block.fee_recipient,
+                ..Default::default()
+            });
+
+            report.extra_data = block.extra_data.clone();
+            report.blocks += 1;
+            report.skipped_transactions += block.skipped_transactions;
+            if block.skipped_transactions > 0 {
+                report.blocks_with_skips += 1;
+            }
+        }
+
+        let mut report = builders.into_values().collect::<Vec<_>>();
+        report.sort_by(|a, b| b.skipped_transactions.cmp(&a.skipped_transactions));
+        report
+    }
+}
+
+/// How often a builder's blocks in the window left out transactions that could have fit
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BuilderSkipReport {
+    /// Builder pubkey, or the fee recipient for blocks the relay didn't deliver
+    pub builder: String,
+    pub fee_recipient: Address,
+    /// Extra data of the builder's latest block in the window
+    pub extra_data: String,
+    pub blocks: u64,
+    pub blocks_with_skips: u64,
+    pub skipped_transactions: u64,
+}
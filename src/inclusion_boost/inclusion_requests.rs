@@ -0,0 +1,179 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{B256, U256},
+    providers::{Provider, RootProvider},
+    transports::http::Http,
+};
+use parking_lot::RwLock;
+
+use super::{error::InclusionListBoostError, types::Transaction};
+
+/// Requests that were not included after this long are dropped
+const INCLUSION_REQUEST_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Why a user submitted transaction was not queued
+#[derive(Debug)]
+pub enum RequestRejection {
+    Invalid(InclusionListBoostError),
+    StaleNonce { nonce: u64, account_nonce: u64 },
+    FutureNonce { nonce: u64, account_nonce: u64 },
+    InsufficientBalance { required: U256, balance: U256 },
+    FeeTooLow { max_fee_per_gas: u128, base_fee: u128 },
+    NoPriorityFee,
+    QueueFull,
+    AlreadyQueued,
+}
+
+impl fmt::Display for RequestRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestRejection::Invalid(err) => write!(f, "invalid transaction: {err:?}"),
+            RequestRejection::StaleNonce { nonce, account_nonce } => {
+                write!(f, "nonce {nonce} already used, account nonce is {account_nonce}")
+            }
+            RequestRejection::FutureNonce { nonce, account_nonce } => {
+                write!(f, "nonce {nonce} is ahead of account nonce {account_nonce}")
+            }
+            RequestRejection::InsufficientBalance { required, balance } => {
+                write!(f, "balance {balance} does not cover {required}")
+            }
+            RequestRejection::FeeTooLow { max_fee_per_gas, base_fee } => {
+                write!(f, "max fee per gas {max_fee_per_gas} is below the base fee {base_fee}")
+            }
+            RequestRejection::NoPriorityFee => write!(f, "transaction pays no priority fee"),
+            RequestRejection::QueueFull => write!(f, "inclusion request queue is full"),
+            RequestRejection::AlreadyQueued => write!(f, "transaction is already queued"),
+        }
+    }
+}
+
+impl From<InclusionListBoostError> for RequestRejection {
+    fn from(value: InclusionListBoostError) -> Self {
+        RequestRejection::Invalid(value)
+    }
+}
+
+#[derive(Debug)]
+struct QueuedRequest {
+    transaction: Transaction,
+    received_at: Instant,
+}
+
+/// Transactions users asked our proposers to include, considered for the
+/// next owned slot alongside the mempool
+#[derive(Debug)]
+pub struct InclusionRequestQueue {
+    capacity: usize,
+    requests: RwLock<VecDeque<QueuedRequest>>,
+}
+
+impl InclusionRequestQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            requests: RwLock::new(VecDeque::new()),
+        }
+    }
+
+    /// Decode and validate a raw signed transaction against the head state, then queue it
+    pub async fn submit(
+        &self,
+        eth_provider: &RootProvider<Http<reqwest::Client>>,
+        raw: alloy::primitives::Bytes,
+    ) -> Result<B256, RequestRejection> {
+        let transaction = Transaction::from_raw(raw)?;
+
+        if self.contains(&transaction.tx_hash) {
+            return Err(RequestRejection::AlreadyQueued);
+        }
+
+        validate_against_head(eth_provider, &transaction).await?;
+
+        let mut requests = self.requests.write();
+        if requests.iter().any(|request| request.transaction.tx_hash == transaction.tx_hash) {
+            return Err(RequestRejection::AlreadyQueued);
+        }
+        if requests.len() >= self.capacity {
+            return Err(RequestRejection::QueueFull);
+        }
+
+        let tx_hash = transaction.tx_hash;
+        requests.push_back(QueuedRequest {
+            transaction,
+            received_at: Instant::now(),
+        });
+
+        Ok(tx_hash)
+    }
+
+    pub fn contains(&self, tx_hash: &B256) -> bool {
+        self.requests.read().iter().any(|request| &request.transaction.tx_hash == tx_hash)
+    }
+
+    /// Queued transactions, oldest first
+    pub fn transactions(&self) -> Vec<Transaction> {
+        self.requests.read().iter().map(|request| request.transaction.clone()).collect()
+    }
+
+    /// Drop requests that made it on chain or expired
+    pub fn prune(&self, included: &HashSet<B256>) {
+        self.requests.write().retain(|request| {
+            !included.contains(&request.transaction.tx_hash) &&
+                request.received_at.elapsed() < INCLUSION_REQUEST_TTL
+        });
+    }
+}
+
+/// Check nonce, balance and fees of `transaction` against the latest block. The balance has to
+/// cover the value and the most the transaction may pay for its gas and blob gas
+async fn validate_against_head(
+    eth_provider: &RootProvider<Http<reqwest::Client>>,
+    transaction: &Transaction,
+) -> Result<(), RequestRejection> {
+    let account_nonce = eth_provider
+        .get_transaction_count(transaction.from)
+        .await
+        .map_err(InclusionListBoostError::from)?;
+
+    if transaction.nonce < account_nonce {
+        return Err(RequestRejection::StaleNonce { nonce: transaction.nonce, account_nonce });
+    }
+    if transaction.nonce > account_nonce {
+        return Err(RequestRejection::FutureNonce { nonce: transaction.nonce, account_nonce });
+    }
+
+    let max_fee_per_gas = transaction.max_fee_per_gas.unwrap_or_default();
+    if !transaction.max_priority_fee_per_gas.is_some_and(|fee| fee > 0) {
+        return Err(RequestRejection::NoPriorityFee);
+    }
+
+    let base_fee = eth_provider
+        .get_block_by_number(BlockNumberOrTag::Latest, false)
+        .await
+        .map_err(InclusionListBoostError::from)?
+        .and_then(|block| block.header.base_fee_per_gas)
+        .unwrap_or_default();
+
+    if max_fee_per_gas < base_fee {
+        return Err(RequestRejection::FeeTooLow { max_fee_per_gas, base_fee });
+    }
+
+    let blob_fee = U256::from(transaction.blob_gas()) * U256::from(transaction.max_fee_per_blob_gas.unwrap_or_default());
+    let required = U256::from(transaction.gas) * U256::from(max_fee_per_gas) + blob_fee + transaction.value;
+    let balance = eth_provider
+        .get_balance(transaction.from)
+        .await
+        .map_err(InclusionListBoostError::from)?;
+
+    if balance < required {
+        return Err(RequestRejection::InsufficientBalance { required, balance });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use std::{collections::HashMap, time::Duration};
+
+use alloy::{
+    providers::{ext::TxPoolApi, ProviderBuilder, RootProvider},
+    rpc,
+    transports::http::Http,
+};
+use futures::future::join_all;
+use parking_lot::RwLock;
+use serde::Serialize;
+
+use super::{
+    aggregation::{Aggregation, Committee},
+    error::InclusionListBoostError,
+    types::Transaction,
+};
+
+/// A node that doesn't answer a poll within this long counts as down for that poll
+pub const POLL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Health of an execution node pending transactions are read from
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SourceHealth {
+    pub url: String,
+    /// Whether the node answered the last poll
+    pub healthy: bool,
+    pub consecutive_failures: u64,
+    pub last_error: Option<String>,
+    /// Unix timestamp of the last successful poll
+    pub last_success: Option<u64>,
+    pub pending_count: usize,
+}
+
+/// The execution nodes whose mempools are merged into the local view
+pub struct MempoolSources {
+    providers: Vec<RootProvider<Http<reqwest::Client>>>,
+    health: RwLock<Vec<SourceHealth>>,
+    aggregation: Aggregation,
+    /// Keep the EIP-2718 encoding of pending transactions, for lists carrying raw bytes
+    encode_raw: bool,
+}
+
+impl MempoolSources {
+    pub fn new(urls: &[String], aggregation: Aggregation, encode_raw: bool) -> Result<Self, InclusionListBoostError> {
+        if urls.is_empty() {
+            return Err("execution_api needs at least one url".to_string().into());
+        }
+
+        let providers = urls
+            .iter()
+            .map(|url| {
+                let parsed = url.parse::<reqwest::Url>().map_err(|err| format!("invalid execution_api url {url}: {err}"))?;
+                Ok(ProviderBuilder::new().on_http(parsed))
+            })
+            .collect::<Result<Vec<_>, InclusionListBoostError>>()?;
+        let health = urls
+            .iter()
+            .map(|url| SourceHealth {
+                url: url.clone(),
+                healthy: true,
+                ..Default::default()
+            })
+            .collect();
+
+        Ok(Self {
+            providers,
+            health: RwLock::new(health),
+            aggregation,
+            encode_raw,
+        })
+    }
+
+    /// Provider of the first node that answered its last poll, the first node if none did
+    pub fn provider(&self) -> &RootProvider<Http<reqwest::Client>> {
+        let index = self.health.read().iter().position(|source| source.healthy).unwrap_or_default();
+        &self.providers[index]
+    }
+
+    pub fn health(&self) -> Vec<SourceHealth> {
+        self.health.read().clone()
+    }
+
+    /// Convert a transaction returned by an execution node, re-encoding it only when lists
+    /// carry raw bytes
+    pub fn from_node(&self, tx: rpc::types::Transaction) -> Transaction {
+        Transaction::from_rpc(tx, self.encode_raw)
+    }
+
+    /// Poll every node and merge their pending transactions. A threshold is lowered to the
+    /// number of nodes that answered, so the view falls back to the remaining nodes while one
+    /// is down. Only fails when no node answered
+    pub async fn pending(&self, now: u64) -> Result<Vec<Transaction>, InclusionListBoostError> {
+        let results = join_all(self.providers.iter().map(|provider| async move {
+            match tokio::time::timeout(POLL_TIMEOUT, provider.txpool_content()).await {
+                Ok(result) => result.map_err(InclusionListBoostError::from),
+                Err(_) => Err("timed out".to_string().into()),
+            }
+        }))
+        .await;
+
+        let mut committee = Committee::default();
+        let mut seen = HashMap::new();
+        let mut answered = 0;
+        let mut last_error = None;
+
+        let mut health = self.health.write();
+        for (source, result) in health.iter_mut().zip(results) {
+            match result {
+                Ok(tx_pool) => {
+                    let pending = tx_pool
+                        .pending
+                        .into_values()
+                        .flat_map(|transactions| transactions.into_values())
+                        .map(|tx| self.from_node(tx))
+                        .collect::<Vec<_>>();
+
+                    source.healthy = true;
+                    source.consecutive_failures = 0;
+                    source.last_success = Some(now);
+                    source.pending_count = pending.len();
+                    answered += 1;
+
+                    committee.vote(&source.url, pending.iter().map(|tx| tx.tx_hash));
+                    seen.extend(pending.into_iter().map(|tx| (tx.tx_hash, tx)));
+                }
+                Err(err) => {
+                    tracing::warn!(?err, url = source.url, "Failed to fetch pending transactions");
+                    source.healthy = false;
+                    source.consecutive_failures += 1;
+                    source.last_error = Some(format!("{err:?}"));
+                    last_error = Some(err);
+                }
+            }
+        }
+        drop(health);
+
+        if answered == 0 {
+            return Err(last_error.expect("at least one source"));
+        }
+
+        let required_votes = self.aggregation.required_votes().min(answered);
+        let listed = committee.aggregate(Aggregation::Threshold(required_votes));
+        let pending = seen.into_values().filter(|tx| listed.contains_key(&tx.tx_hash)).collect::<Vec<_>>();
+
+        tracing::info!(
+            source_count = self.providers.len(),
+            answered,
+            required_votes,
+            transaction_count = pending.len(),
+            "Fetched pending transactions from the memory pools"
+        );
+
+        Ok(pending)
+    }
+}
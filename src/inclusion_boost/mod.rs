@@ -1,22 +1,64 @@
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
 use alloy::{
-    hex::ToHexExt, primitives::Bytes, rpc::types::{
+    hex::ToHexExt, primitives::{Bytes, B256}, rpc::types::{
         beacon::{BlsPublicKey, BlsSignature},
-        Block,
+        Block, Header,
     }
 };
-use cb_common::commit::{client::SignerClient, error::SignerClientError, request::SignRequest};
+use cb_common::commit::{
+    client::SignerClient,
+    request::{SignRequest, SignedProxyDelegation},
+};
+use clock::SECONDS_PER_SLOT;
+use delegation::Delegation;
 use error::InclusionListBoostError;
+use fees::{BlobParams, BlobSchedule, NextBlockFees};
+use focil::{FocilInclusionList, SignedFocilInclusionList};
+use history::{BlockHistory, BlockRecord};
+use parking_lot::RwLock;
+use protection::ProtectionDb;
+use sanctions::SanctionsTag;
+use selection::{effective_tip, Candidate, CensorshipEvidence, GasBudget};
+use serde::Serialize;
 use tree_hash::TreeHash;
-use types::{Constraint, InclusionList, InclusionListDelegateMessage, InclusionListDelegateSignedMessage, InclusionRequest, Transaction};
+use types::{
+    Constraint, InclusionList, InclusionListDelegateMessage, InclusionListDelegateSignedMessage, InclusionListRevokeMessage,
+    InclusionListRevokeSignedMessage, InclusionRequest, Transaction,
+};
 
+pub mod aggregation;
+pub mod clock;
+pub mod delegation;
 pub mod error;
+pub mod fees;
+pub mod focil;
+pub mod history;
+pub mod inclusion_requests;
+pub mod mempool;
+pub mod nonces;
+pub mod policy;
+pub mod protection;
+pub mod proxy;
+pub mod sanctions;
+pub mod satisfaction;
+pub mod selection;
 pub mod sidecar;
+pub mod simulation;
+pub mod supervisor;
 pub mod types;
+pub mod util;
 
 const CONSTRAINTS_PATH: &str = "/eth/v1/builder/set_constraints";
 const DELEGATE_PATH: &str = "/eth/v1/builder/elect_preconfer";
+const REVOKE_PATH: &str = "/eth/v1/builder/revoke_preconfer";
+const FOCIL_INCLUSION_LIST_PATH: &str = "/eth/v1/builder/inclusion_list";
+/// Chain id delegations and revocations are signed for
+const CHAIN_ID: u64 = 7014190335;
 
 /// Implements an inclusion list flavor
 /// of commit-boost
@@ -24,6 +66,10 @@ pub struct InclusionBoost {
     pub module_id: String,
     pub signer_client: SignerClient,
     pub validator_keys: HashMap<usize, BlsPublicKey>,
+    /// Proxy keys that sign inclusion lists in place of the validator keys
+    pub proxy_delegations: HashMap<usize, SignedProxyDelegation>,
+    /// Roots signed so far, checked before anything is signed
+    pub protection: Arc<RwLock<ProtectionDb>>,
     pub relay_client: reqwest::Client,
     pub relay_url: String,
 }
@@ -57,6 +103,8 @@ impl InclusionBoost {
             module_id,
             signer_client,
             validator_keys,
+            proxy_delegations: HashMap::new(),
+            protection: Arc::new(RwLock::new(ProtectionDb::default())),
             relay_client: reqwest::Client::new(),
             relay_url,
         }
@@ -68,52 +116,116 @@ impl InclusionBoost {
         transactions: &Vec<Transaction>,
         block: &Block<alloy::rpc::types::Transaction>,
     ) -> Vec<Constraint> {
-        let mut filtered_transactions = vec![];
-        let mut gas_left = block.header.gas_limit - block.header.gas_used;
-        
+        Self::select_transactions(transactions, block)
+            .into_iter()
+            .map(Transaction::to_constraint)
+            .collect()
+    }
+
+    /// Same as `get_filtered_transactions` but returns the selected transactions themselves,
+    /// using the gas left in `block` as the budget
+    pub fn select_transactions<'a>(
+        transactions: &'a [Transaction],
+        block: &Block<alloy::rpc::types::Transaction>,
+    ) -> Vec<&'a Transaction> {
+        let mut history = BlockHistory::new(1);
+        history.insert(BlockRecord::from_block(block));
+
+        Self::select_candidates(
+            transactions,
+            &block.header,
+            GasBudget::Remaining,
+            BlobSchedule::default().after(&block.header, SECONDS_PER_SLOT),
+            &history,
+            &HashMap::new(),
+            |_| None,
+            0,
+        )
+            .into_iter()
+            .map(|candidate| candidate.tx)
+            .collect()
+    }
+
+    /// Pick the transactions with the strongest censorship evidence that fit in `gas_budget`
+    /// and the next block's blob gas limit under `blob_params`. `transactions` must be in nonce order per sender,
+    /// once a transaction can't pay a tip the rest of its sender's transactions are skipped.
+    /// `first_seen` is the unix time a transaction entered the mempool, if known
+    pub fn select_candidates<'a>(
+        transactions: &'a [Transaction],
+        parent: &Header,
+        gas_budget: GasBudget,
+        blob_params: BlobParams,
+        history: &BlockHistory,
+        sanctions_tags: &HashMap<B256, Vec<SanctionsTag>>,
+        first_seen: impl Fn(&Transaction) -> Option<u64>,
+        now: u64,
+    ) -> Vec<Candidate<'a>> {
+        let next_base_fee = NextBlockFees::from_parent(parent, 0, blob_params).base_fee;
+        let mut skipped_senders = HashSet::new();
+        let mut candidates = vec![];
+
         for tx in transactions {
-            if let Some(max_priority_fee_per_gas) = tx.max_priority_fee_per_gas {
-                if max_priority_fee_per_gas > 0 && gas_left > 0 {
-                    gas_left = gas_left.saturating_sub(tx.gas);
-              
-                    filtered_transactions.push(Constraint {
-                        tx: bytes_to_array(tx.bytes.clone()),
-                    });
-                    tracing::info!(
-                        tx_hash = ?tx.tx_hash,
-                        "Added transaction to inclusion list"
-                    );
-                }
+            if skipped_senders.contains(&tx.from) {
+                continue;
             }
+
+            let effective_tip = effective_tip(tx, next_base_fee);
+            if effective_tip == 0 {
+                skipped_senders.insert(tx.from);
+                continue;
+            }
+
+            let first_seen = first_seen(tx);
+            candidates.push(Candidate {
+                tx,
+                evidence: CensorshipEvidence {
+                    blocks_skipped: history.blocks_skipped(tx, first_seen),
+                    seconds_pending: first_seen.map_or(0, |seen| now.saturating_sub(seen)),
+                    effective_tip,
+                    sanctions_exposed: sanctions_tags.contains_key(&tx.tx_hash),
+                },
+            });
         }
 
-        filtered_transactions
+        let max_blob_gas = blob_params.max_blob_gas();
+        let selected = selection::select(candidates, gas_budget.resolve(parent), max_blob_gas);
+
+        for candidate in &selected {
+            tracing::info!(
+                tx_hash = ?candidate.tx.tx_hash,
+                blocks_skipped = candidate.evidence.blocks_skipped,
+                seconds_pending = candidate.evidence.seconds_pending,
+                effective_tip = candidate.evidence.effective_tip,
+                sanctions_exposed = candidate.evidence.sanctions_exposed,
+                "Added transaction to inclusion list"
+            );
+        }
+
+        selected
     }
 
     pub async fn delegate_inclusion_list_authority(
         &self,
-        validator_index: usize,
-        slot: u64,
+        delegation: &Delegation,
     ) -> Result<Option<()>, InclusionListBoostError>{
-        let Some(validator_key) = self.validator_keys.get(&validator_index) else {
-            return Ok(None);
-        };
-
         tracing::info!(
-            validator_index,
-            slot,
+            validator_index = delegation.validator_index,
+            slot = delegation.slot,
+            relay = delegation.relay,
             "Delegating inclusion list building responsibilities to IL Boost"
         );
 
         let message = InclusionListDelegateMessage {
-            preconfer_pubkey: validator_key.clone(),
-            slot_number: slot,
-            chain_id: 7014190335,
+            preconfer_pubkey: delegation.delegate_pubkey.clone(),
+            slot_number: delegation.slot,
+            chain_id: CHAIN_ID,
             gas_limit: u64::MAX,
         };
 
         let message_root = message.tree_hash_root();
-        let sign_request = SignRequest::builder(validator_key.clone())
+        self.protection.write().check_delegation(delegation.validator_pubkey, delegation.slot, message_root)?;
+
+        let sign_request = SignRequest::builder(delegation.validator_pubkey.clone())
             .with_root(message_root.into());
 
         let signature = self.signer_client.request_signature(&sign_request).await?;
@@ -123,7 +235,39 @@ impl InclusionBoost {
             signature
         };
 
-        self.post_inclusion_delegate_request(signed_message).await
+        self.post_to_relay(&delegation.relay, DELEGATE_PATH, &signed_message).await
+    }
+
+    /// Withdraw an earlier delegation, signed by the same proposer key
+    pub async fn revoke_inclusion_list_authority(
+        &self,
+        delegation: &Delegation,
+    ) -> Result<Option<()>, InclusionListBoostError> {
+        tracing::info!(
+            validator_index = delegation.validator_index,
+            slot = delegation.slot,
+            relay = delegation.relay,
+            "Revoking inclusion list delegation"
+        );
+
+        let message = InclusionListRevokeMessage {
+            preconfer_pubkey: delegation.delegate_pubkey.clone(),
+            slot_number: delegation.slot,
+            chain_id: CHAIN_ID,
+        };
+
+        let sign_request =
+            SignRequest::builder(delegation.validator_pubkey.clone()).with_root(message.tree_hash_root().into());
+        let signature = self.signer_client.request_signature(&sign_request).await?;
+
+        let signed_message = InclusionListRevokeSignedMessage { message, signature };
+
+        let revoked = self.post_to_relay(&delegation.relay, REVOKE_PATH, &signed_message).await?;
+        if revoked.is_some() {
+            self.protection.write().record_revocation(delegation.validator_pubkey, delegation.slot);
+        }
+
+        Ok(revoked)
     }
 
     /// Submit the inclusion list to the relay
@@ -145,17 +289,20 @@ impl InclusionBoost {
         );
 
 
-        let signature = self
-            .sign_inclusion_list(&inclusion_list, *validator_key)
-            .await?;
+        let proxy_delegation = self.proxy_delegations.get(&validator_index).cloned();
+        let signature = match &proxy_delegation {
+            Some(delegation) => self.sign_inclusion_list(&inclusion_list, delegation.message.proxy, true).await?,
+            None => self.sign_inclusion_list(&inclusion_list, *validator_key, false).await?,
+        };
 
         tracing::info!(
+            proxy = proxy_delegation.is_some(),
             "Inclusion list signed"
         );
 
-        match self.post_inclusion_request(signature, inclusion_list)
+        match self.post_inclusion_request(signature, inclusion_list, proxy_delegation)
             .await {
-                Ok(res) => println!("{:?}", res),
+                Ok(res) => tracing::debug!(accepted = res.is_some(), "Relay answered inclusion list"),
                 Err(e) => return Err(e)
             };
 
@@ -166,31 +313,110 @@ impl InclusionBoost {
         Ok(Some(()))
     }
 
+    /// Submit a list for a slot whose authority was delegated to `delegate_key`, a proxy key of
+    /// the signer. The validator key isn't touched
+    pub async fn submit_delegated_inclusion_list(
+        &self,
+        delegate_key: BlsPublicKey,
+        inclusion_list: InclusionList,
+    ) -> Result<Option<()>, InclusionListBoostError> {
+        tracing::info!(
+            validator_index = inclusion_list.validator_index,
+            %delegate_key,
+            "Submitting delegated inclusion list to relay"
+        );
+
+        let signature = self.sign_inclusion_list(&inclusion_list, delegate_key, true).await?;
+
+        self.post_inclusion_request(signature, inclusion_list, None).await
+    }
+
+    /// Sign an EIP-7805 list over its `DOMAIN_INCLUSION_LIST_COMMITTEE` signing root and send it
+    /// to the relay. The commit-boost signer mixes in its own domain on top of that root, so the
+    /// signature verifies against the commit-boost domain rather than the bare EIP-7805 one
+    pub async fn submit_focil_inclusion_list(
+        &self,
+        inclusion_list: FocilInclusionList,
+        domain: [u8; 32],
+    ) -> Result<Option<()>, InclusionListBoostError> {
+        let Some(validator_key) = self.validator_keys.get(&(inclusion_list.validator_index as usize)) else {
+            return Ok(None);
+        };
+
+        tracing::info!(
+            validator_index = inclusion_list.validator_index,
+            transaction_count = inclusion_list.transactions.len(),
+            "Submitting EIP-7805 inclusion list to relay"
+        );
+
+        let signing_root = inclusion_list.signing_root(domain);
+        self.protection.write().check_inclusion_list(*validator_key, inclusion_list.slot, signing_root, 0)?;
+
+        let sign_request = SignRequest::builder(*validator_key)
+            .with_root(signing_root.into());
+        let signature = self.signer_client.request_signature(&sign_request).await?;
+
+        let signed_list = SignedFocilInclusionList {
+            message: inclusion_list,
+            signature,
+        };
+
+        let url = format!("{}{FOCIL_INCLUSION_LIST_PATH}", self.relay_url);
+        tracing::info!(url, payload=?signed_list, "POST request sent");
+
+        let response = self.relay_client.post(url).timeout(Duration::from_secs(10)).json(&signed_list).send().await?;
+        let status = response.status();
+        let response_bytes = response.bytes().await?;
+
+        if !status.is_success() {
+            let err = String::from_utf8_lossy(&response_bytes).into_owned();
+            tracing::error!(err, "failed to submit EIP-7805 inclusion list");
+            return Ok(None);
+        }
+
+        Ok(Some(()))
+    }
+
     /// Sign an inclusion list via the commit-boost signing module
     async fn sign_inclusion_list(
         &self,
         inclusion_list: &InclusionList,
-        validator_key: BlsPublicKey,
-    ) -> Result<BlsSignature, SignerClientError> {
+        signing_key: BlsPublicKey,
+        is_proxy: bool,
+    ) -> Result<BlsSignature, InclusionListBoostError> {
         let inclusion_list_root = inclusion_list.tree_hash_root();
-        let sign_request = SignRequest::builder(validator_key)
+        let validator_key = self.validator_keys.get(&inclusion_list.validator_index).copied().unwrap_or(signing_key);
+        self.protection.write().check_inclusion_list(
+            validator_key,
+            inclusion_list.slot,
+            inclusion_list_root,
+            inclusion_list.revision,
+        )?;
+
+        let mut sign_request = SignRequest::builder(signing_key)
             .with_root(inclusion_list_root.into());
+        if is_proxy {
+            sign_request = sign_request.is_proxy();
+        }
 
-        self.signer_client.request_signature(&sign_request).await
+        Ok(self.signer_client.request_signature(&sign_request).await?)
     }
 
-    async fn post_inclusion_delegate_request(
+    /// Post a signed delegation or revocation to `relay`
+    async fn post_to_relay(
         &self,
-        signed_message: InclusionListDelegateSignedMessage
+        relay: &str,
+        path: &str,
+        signed_message: &impl Serialize,
     ) -> Result<Option<()>, InclusionListBoostError> {
-        let url = format!("{}{DELEGATE_PATH}", self.relay_url);
+        let url = format!("{relay}{path}");
 
-        tracing::info!(url, payload=?signed_message, "POST request sent");
+        tracing::info!(url, "POST request sent");
 
-        let response = match self.relay_client.post(url).timeout(Duration::from_secs(10)).json(&signed_message).send().await {
+        let response = match self.relay_client.post(url).timeout(Duration::from_secs(10)).json(signed_message).send().await {
             Ok(res) => res,
             Err(e) => {
-                println!("{:?}", e);
+                tracing::warn!(err = ?e, path, "Failed to post to relay");
                 return Err(e.into())
             }
         };
@@ -213,12 +439,14 @@ impl InclusionBoost {
         &self,
         signature: BlsSignature,
         inclusion_list: InclusionList,
+        proxy_delegation: Option<SignedProxyDelegation>,
     ) -> Result<Option<()>, InclusionListBoostError> {
         let url = format!("{}{CONSTRAINTS_PATH}", self.relay_url);
 
         let request = InclusionRequest {
             message: inclusion_list,
             signature,
+            proxy_delegation,
         };
 
 
@@ -227,7 +455,7 @@ impl InclusionBoost {
         let response = match self.relay_client.post(url).timeout(Duration::from_secs(10)).json(&request).send().await {
             Ok(res) => res,
             Err(e) => {
-                println!("{:?}", e);
+                tracing::warn!(err = ?e, slot = request.message.slot, "Failed to post inclusion list to relay");
                 return Err(e.into())
             }
         };
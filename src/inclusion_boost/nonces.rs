@@ -0,0 +1,129 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use alloy::{
+    primitives::Address,
+    providers::{Provider, RootProvider},
+    transports::http::Http,
+};
+use futures::{stream, StreamExt};
+
+use super::types::{DropReason, DroppedCandidate, Transaction};
+
+/// Nonce lookups in flight at once
+const NONCE_LOOKUP_CONCURRENCY: usize = 16;
+
+/// Senders' nonces at head, or why the lookup failed
+pub type AccountNonces = BTreeMap<Address, Result<u64, String>>;
+
+/// Order candidates so that every sender's transactions form a gapless nonce sequence starting
+/// at the sender's nonce at head. Senders are ordered by address so the result is deterministic.
+/// The transactions of senders whose nonce couldn't be looked up are dropped
+pub async fn order_by_nonce(
+    eth_provider: &RootProvider<Http<reqwest::Client>>,
+    transactions: Vec<Transaction>,
+) -> (Vec<Transaction>, Vec<DroppedCandidate>) {
+    let mut account_nonces = AccountNonces::new();
+    lookup_nonces(eth_provider, transactions.iter().map(|tx| tx.from), &mut account_nonces).await;
+    order_with_nonces(&account_nonces, transactions)
+}
+
+/// Look up the nonce at head of every sender that isn't in `account_nonces` yet, so views that
+/// share senders are ordered against a single lookup
+pub async fn lookup_nonces(
+    eth_provider: &RootProvider<Http<reqwest::Client>>,
+    senders: impl IntoIterator<Item = Address>,
+    account_nonces: &mut AccountNonces,
+) {
+    let missing = senders
+        .into_iter()
+        .filter(|sender| !account_nonces.contains_key(sender))
+        .collect::<BTreeSet<_>>();
+
+    let looked_up = stream::iter(missing)
+        .map(|sender| async move {
+            let nonce = eth_provider.get_transaction_count(sender).await.map_err(|err| {
+                tracing::warn!(?err, %sender, "Failed to look up the sender's nonce");
+                err.to_string()
+            });
+            (sender, nonce)
+        })
+        .buffer_unordered(NONCE_LOOKUP_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+    account_nonces.extend(looked_up);
+}
+
+/// `order_by_nonce` with nonces that were already looked up. Senders without a nonce are dropped
+pub fn order_with_nonces(
+    account_nonces: &AccountNonces,
+    transactions: Vec<Transaction>,
+) -> (Vec<Transaction>, Vec<DroppedCandidate>) {
+    let mut by_sender: BTreeMap<Address, Vec<Transaction>> = BTreeMap::new();
+    for tx in transactions {
+        by_sender.entry(tx.from).or_default().push(tx);
+    }
+
+    let mut ordered = vec![];
+    let mut dropped = vec![];
+
+    for (sender, sender_txs) in by_sender {
+        match account_nonces.get(&sender) {
+            Some(Ok(account_nonce)) => {
+                let (executable, gapped) = order_sender_transactions(*account_nonce, sender_txs);
+                ordered.extend(executable);
+                dropped.extend(gapped);
+            }
+            lookup => {
+                let error = lookup
+                    .and_then(|nonce| nonce.clone().err())
+                    .unwrap_or_else(|| "nonce wasn't looked up".to_string());
+                dropped.extend(sender_txs.into_iter().map(|tx| DroppedCandidate {
+                    tx_hash: tx.tx_hash,
+                    reason: DropReason::NonceLookupFailed { error: error.clone() },
+                }));
+            }
+        }
+    }
+
+    (ordered, dropped)
+}
+
+/// Keep the run of `transactions` that continues from `account_nonce` without gaps.
+/// When two transactions share a nonce the one with the higher priority fee is kept
+pub fn order_sender_transactions(
+    account_nonce: u64,
+    mut transactions: Vec<Transaction>,
+) -> (Vec<Transaction>, Vec<DroppedCandidate>) {
+    transactions.sort_by(|a, b| {
+        a.nonce
+            .cmp(&b.nonce)
+            .then(b.max_priority_fee_per_gas.cmp(&a.max_priority_fee_per_gas))
+            .then(a.tx_hash.cmp(&b.tx_hash))
+    });
+
+    let mut expected_nonce = account_nonce;
+    let mut executable = vec![];
+    let mut dropped = vec![];
+
+    for tx in transactions {
+        let reason = if tx.nonce < account_nonce {
+            DropReason::StaleNonce { account_nonce }
+        } else if tx.nonce < expected_nonce {
+            DropReason::DuplicateNonce
+        } else if tx.nonce > expected_nonce {
+            DropReason::NonceGap { expected_nonce }
+        } else {
+            expected_nonce += 1;
+            executable.push(tx);
+            continue;
+        };
+
+        dropped.push(DroppedCandidate {
+            tx_hash: tx.tx_hash,
+            reason,
+        });
+    }
+
+    (executable, dropped)
+}
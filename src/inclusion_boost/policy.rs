@@ -0,0 +1,125 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+};
+
+use alloy::primitives::Address;
+use serde::Deserialize;
+
+use super::{
+    error::InclusionListBoostError,
+    types::{DropReason, DroppedCandidate, Transaction},
+};
+
+/// Operator defined rules deciding which pending transactions may become constraints.
+/// Empty allow lists allow everything, unset limits are not enforced
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct InclusionPolicy {
+    pub deny_senders: HashSet<Address>,
+    pub allow_senders: HashSet<Address>,
+    pub deny_contracts: HashSet<Address>,
+    pub allow_contracts: HashSet<Address>,
+    pub max_constraints_per_sender: Option<usize>,
+    pub min_priority_fee_per_gas: Option<u128>,
+    pub max_calldata_size: Option<usize>,
+    pub excluded_tx_types: HashSet<u8>,
+}
+
+impl InclusionPolicy {
+    /// Load a policy from a TOML file
+    pub fn load(path: &str) -> Result<Self, InclusionListBoostError> {
+        let policy_str = fs::read_to_string(path)?;
+        toml::from_str(&policy_str)
+            .map_err(|err| format!("failed to parse policy file {path}: {err}").into())
+    }
+
+    /// Split `transactions` into the ones the policy accepts and the ones it drops.
+    /// The per sender limit is applied separately by `limit_per_sender`
+    pub fn apply(&self, transactions: Vec<Transaction>) -> (Vec<Transaction>, Vec<DroppedCandidate>) {
+        let mut accepted = vec![];
+        let mut dropped = vec![];
+
+        for tx in transactions {
+            match self.evaluate(&tx) {
+                Ok(()) => accepted.push(tx),
+                Err(reason) => {
+                    tracing::debug!(tx_hash = ?tx.tx_hash, ?reason, "Policy dropped candidate");
+                    dropped.push(DroppedCandidate {
+                        tx_hash: tx.tx_hash,
+                        reason,
+                    });
+                }
+            }
+        }
+
+        (accepted, dropped)
+    }
+
+    /// Keep the `max_constraints_per_sender` lowest nonce transactions of every sender, in the
+    /// order they are given. Applied after nonce ordering, so that stale or gapped transactions
+    /// don't use up the limit and no kept transaction depends on a dropped one
+    pub fn limit_per_sender(&self, transactions: Vec<Transaction>) -> (Vec<Transaction>, Vec<DroppedCandidate>) {
+        let Some(limit) = self.max_constraints_per_sender else {
+            return (transactions, vec![]);
+        };
+
+        let mut by_sender: HashMap<Address, Vec<usize>> = HashMap::new();
+        for (index, tx) in transactions.iter().enumerate() {
+            by_sender.entry(tx.from).or_default().push(index);
+        }
+
+        let mut kept = vec![false; transactions.len()];
+        for mut indices in by_sender.into_values() {
+            indices.sort_by_key(|index| transactions[*index].nonce);
+            indices.into_iter().take(limit).for_each(|index| kept[index] = true);
+        }
+
+        let (accepted, limited): (Vec<_>, Vec<_>) = transactions.into_iter().zip(kept).partition(|(_, kept)| *kept);
+        let dropped = limited
+            .into_iter()
+            .map(|(tx, _)| DroppedCandidate {
+                tx_hash: tx.tx_hash,
+                reason: DropReason::SenderLimit { limit },
+            })
+            .collect();
+
+        (accepted.into_iter().map(|(tx, _)| tx).collect(), dropped)
+    }
+
+    fn evaluate(&self, tx: &Transaction) -> Result<(), DropReason> {
+        if self.deny_senders.contains(&tx.from) {
+            return Err(DropReason::DeniedSender);
+        }
+        if !self.allow_senders.is_empty() && !self.allow_senders.contains(&tx.from) {
+            return Err(DropReason::SenderNotAllowed);
+        }
+
+        if tx.to.is_some_and(|to| self.deny_contracts.contains(&to)) {
+            return Err(DropReason::DeniedContract);
+        }
+        if !self.allow_contracts.is_empty() &&
+            !tx.to.is_some_and(|to| self.allow_contracts.contains(&to))
+        {
+            return Err(DropReason::ContractNotAllowed);
+        }
+
+        if self.excluded_tx_types.contains(&tx.tx_type) {
+            return Err(DropReason::ExcludedType { tx_type: tx.tx_type });
+        }
+
+        if let Some(min) = self.min_priority_fee_per_gas {
+            if tx.max_priority_fee_per_gas.unwrap_or_default() < min {
+                return Err(DropReason::TipTooLow { min });
+            }
+        }
+
+        if let Some(max) = self.max_calldata_size {
+            if tx.bytes.len() > max {
+                return Err(DropReason::CalldataTooLarge { size: tx.bytes.len(), max });
+            }
+        }
+
+        Ok(())
+    }
+}
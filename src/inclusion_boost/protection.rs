@@ -0,0 +1,297 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use alloy::{primitives::B256, rpc::types::beacon::BlsPublicKey};
+use serde::{Deserialize, Serialize};
+
+use super::{error::InclusionListBoostError, util::write_json};
+
+pub const INTERCHANGE_FORMAT_VERSION: &str = "1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SignedInclusionListRecord {
+    #[serde(with = "serde_utils::quoted_u64")]
+    pub slot: u64,
+    pub signing_root: B256,
+    #[serde(with = "serde_utils::quoted_u64")]
+    pub revision: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SignedDelegationRecord {
+    #[serde(with = "serde_utils::quoted_u64")]
+    pub slot: u64,
+    pub signing_root: B256,
+    /// A revoked delegation may be replaced by a different one
+    pub revoked: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterchangeMetadata {
+    pub interchange_format_version: String,
+    /// Records before this slot were pruned, nothing before it is signed anymore
+    #[serde(default, with = "serde_utils::quoted_u64")]
+    pub pruned_before: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterchangeValidator {
+    pub pubkey: BlsPublicKey,
+    pub signed_inclusion_lists: Vec<SignedInclusionListRecord>,
+    pub signed_delegations: Vec<SignedDelegationRecord>,
+}
+
+/// Signing history in the spirit of the EIP-3076 slashing protection interchange format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interchange {
+    pub metadata: InterchangeMetadata,
+    pub data: Vec<InterchangeValidator>,
+}
+
+#[derive(Debug, Default)]
+struct ValidatorHistory {
+    inclusion_lists: BTreeMap<u64, SignedInclusionListRecord>,
+    delegations: BTreeMap<u64, SignedDelegationRecord>,
+}
+
+/// Every inclusion list and delegation root signed per validator and slot, so that no two
+/// conflicting messages are ever signed for the same slot, even across restarts or by another
+/// instance that imported this history
+#[derive(Debug, Default)]
+pub struct ProtectionDb {
+    path: Option<String>,
+    validators: BTreeMap<BlsPublicKey, ValidatorHistory>,
+    /// Low watermark standing in for the pruned records
+    pruned_before: u64,
+}
+
+impl ProtectionDb {
+    /// Start from the history saved at `path`, or from scratch if there is none yet
+    pub fn load(path: Option<String>) -> Result<Self, InclusionListBoostError> {
+        let mut db = Self {
+            path,
+            ..Default::default()
+        };
+
+        if let Some(path) = db.path.clone().filter(|path| Path::new(path).exists()) {
+            let interchange: Interchange = serde_json::from_str(&fs::read_to_string(&path)?)?;
+            db.import(interchange)?;
+            tracing::info!(path, validators = db.validators.len(), "Loaded signing protection database");
+        }
+
+        Ok(db)
+    }
+
+    /// Record an inclusion list about to be signed. A different list for the same slot is
+    /// refused unless it has a higher revision, signing the same list again is allowed
+    pub fn check_inclusion_list(
+        &mut self,
+        pubkey: BlsPublicKey,
+        slot: u64,
+        signing_root: B256,
+        revision: u64,
+    ) -> Result<(), InclusionListBoostError> {
+        self.check_watermark(slot)?;
+        let history = self.validators.entry(pubkey).or_default();
+
+        if let Some(previous) = history.inclusion_lists.get(&slot) {
+            if previous.signing_root == signing_root {
+                return Ok(());
+            }
+            if revision <= previous.revision {
+                return Err(InclusionListBoostError::ConflictingSignature(format!(
+                    "inclusion list for slot {slot} conflicts with signed revision {}",
+                    previous.revision
+                )));
+            }
+        }
+
+        history.inclusion_lists.insert(
+            slot,
+            SignedInclusionListRecord {
+                slot,
+                signing_root,
+                revision,
+            },
+        );
+        self.persist()
+    }
+
+    /// Record a delegation about to be signed. A different delegation for the same slot is
+    /// refused until the earlier one was revoked
+    pub fn check_delegation(
+        &mut self,
+        pubkey: BlsPublicKey,
+        slot: u64,
+        signing_root: B256,
+    ) -> Result<(), InclusionListBoostError> {
+        self.check_watermark(slot)?;
+        let history = self.validators.entry(pubkey).or_default();
+
+        if let Some(previous) = history.delegations.get(&slot) {
+            if previous.signing_root == signing_root && !previous.revoked {
+                return Ok(());
+            }
+            if !previous.revoked {
+                return Err(InclusionListBoostError::ConflictingSignature(format!(
+                    "delegation for slot {slot} conflicts with an unrevoked delegation"
+                )));
+            }
+        }
+
+        history.delegations.insert(
+            slot,
+            SignedDelegationRecord {
+                slot,
+                signing_root,
+                revoked: false,
+            },
+        );
+        self.persist()
+    }
+
+    /// Record that the delegation of `slot` is being revoked
+    pub fn record_revocation(&mut self, pubkey: BlsPublicKey, slot: u64) {
+        let history = self.validators.entry(pubkey).or_default();
+        if let Some(delegation) = history.delegations.get_mut(&slot) {
+            delegation.revoked = true;
+            if let Err(err) = self.persist() {
+                tracing::error!(?err, slot, "Failed to persist signing protection database");
+            }
+        }
+    }
+
+    /// Forget records of slots before `slot`. Signing for those slots is refused from now on,
+    /// so the history stays bounded without losing protection
+    pub fn prune(&mut self, slot: u64) {
+        if slot <= self.pruned_before {
+            return;
+        }
+        self.pruned_before = slot;
+
+        for history in self.validators.values_mut() {
+            history.inclusion_lists.retain(|s, _| *s >= slot);
+            history.delegations.retain(|s, _| *s >= slot);
+        }
+        self.validators.retain(|_, history| !history.inclusion_lists.is_empty() || !history.delegations.is_empty());
+
+        if let Err(err) = self.persist() {
+            tracing::error!(?err, slot, "Failed to persist signing protection database");
+        }
+    }
+
+    pub fn export(&self) -> Interchange {
+        Interchange {
+            metadata: InterchangeMetadata {
+                interchange_format_version: INTERCHANGE_FORMAT_VERSION.to_string(),
+                pruned_before: self.pruned_before,
+            },
+            data: self
+                .validators
+                .iter()
+                .map(|(pubkey, history)| InterchangeValidator {
+                    pubkey: *pubkey,
+                    signed_inclusion_lists: history.inclusion_lists.values().copied().collect(),
+                    signed_delegations: history.delegations.values().copied().collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Merge another history into this one, keeping the highest revision of every list and
+    /// any unrevoked delegation. Returns the number of records taken from `interchange`.
+    /// A history that signed a different root for a list revision or an unrevoked delegation
+    /// this one also signed is refused as a whole, as one of the two instances double-signed
+    pub fn import(&mut self, interchange: Interchange) -> Result<usize, InclusionListBoostError> {
+        if interchange.metadata.interchange_format_version != INTERCHANGE_FORMAT_VERSION {
+            return Err(format!(
+                "unsupported interchange format version {}",
+                interchange.metadata.interchange_format_version
+            )
+            .into());
+        }
+
+        for validator in &interchange.data {
+            self.check_import_conflicts(validator)?;
+        }
+
+        self.pruned_before = self.pruned_before.max(interchange.metadata.pruned_before);
+
+        let mut imported = 0;
+        for validator in interchange.data {
+            let history = self.validators.entry(validator.pubkey).or_default();
+
+            for record in validator.signed_inclusion_lists {
+                let newer = history
+                    .inclusion_lists
+                    .get(&record.slot)
+                    .map_or(true, |existing| record.revision > existing.revision);
+                if newer {
+                    history.inclusion_lists.insert(record.slot, record);
+                    imported += 1;
+                }
+            }
+
+            for record in validator.signed_delegations {
+                let replaces = history
+                    .delegations
+                    .get(&record.slot)
+                    .map_or(true, |existing| existing.revoked && !record.revoked);
+                if replaces {
+                    history.delegations.insert(record.slot, record);
+                    imported += 1;
+                }
+            }
+        }
+
+        self.persist()?;
+        Ok(imported)
+    }
+
+    fn check_import_conflicts(&self, validator: &InterchangeValidator) -> Result<(), InclusionListBoostError> {
+        let Some(history) = self.validators.get(&validator.pubkey) else {
+            return Ok(());
+        };
+
+        for record in &validator.signed_inclusion_lists {
+            if let Some(existing) = history.inclusion_lists.get(&record.slot) {
+                if existing.revision == record.revision && existing.signing_root != record.signing_root {
+                    return Err(InclusionListBoostError::ConflictingSignature(format!(
+                        "imported inclusion list for slot {} conflicts with signed revision {}",
+                        record.slot, existing.revision
+                    )));
+                }
+            }
+        }
+
+        for record in &validator.signed_delegations {
+            if let Some(existing) = history.delegations.get(&record.slot) {
+                if !existing.revoked && !record.revoked && existing.signing_root != record.signing_root {
+                    return Err(InclusionListBoostError::ConflictingSignature(format!(
+                        "imported delegation for slot {} conflicts with an unrevoked delegation",
+                        record.slot
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_watermark(&self, slot: u64) -> Result<(), InclusionListBoostError> {
+        if slot < self.pruned_before {
+            return Err(InclusionListBoostError::ConflictingSignature(format!(
+                "slot {slot} is before the pruned history, which starts at slot {}",
+                self.pruned_before
+            )));
+        }
+        Ok(())
+    }
+
+    /// Write the history to the database file. Nothing may be signed when this fails
+    fn persist(&self) -> Result<(), InclusionListBoostError> {
+        match self.path.as_deref() {
+            Some(path) => write_json(path, &self.export()),
+            None => Ok(()),
+        }
+    }
+}
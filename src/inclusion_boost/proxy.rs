@@ -0,0 +1,67 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use alloy::rpc::types::beacon::BlsPublicKey;
+use cb_common::commit::{client::SignerClient, request::SignedProxyDelegation};
+
+use super::{error::InclusionListBoostError, util::write_json};
+
+/// Proxy key of every validator, reusing the ones saved at `path` that the signer still
+/// holds and generating the rest. The signer only hands out a delegation when generating
+/// its key, so keys that aren't saved can't be reused. Validators whose proxy key can't be
+/// generated are left out and keep signing with their own key
+pub async fn load_proxy_keys(
+    signer_client: &SignerClient,
+    validator_keys: &HashMap<usize, BlsPublicKey>,
+    signer_proxy_keys: &[BlsPublicKey],
+    path: &str,
+) -> Result<HashMap<usize, SignedProxyDelegation>, InclusionListBoostError> {
+    let saved: HashMap<usize, SignedProxyDelegation> = match Path::new(path).exists() {
+        true => serde_json::from_str(&fs::read_to_string(path)?)?,
+        false => HashMap::new(),
+    };
+
+    let (mut proxy_delegations, missing) = reusable_delegations(saved, validator_keys, signer_proxy_keys);
+    for (validator_index, validator_key) in missing {
+        match signer_client.generate_proxy_key(validator_key).await {
+            Ok(delegation) => {
+                tracing::info!(validator_index, proxy = %delegation.message.proxy, "Generated proxy key");
+                proxy_delegations.insert(validator_index, delegation);
+            }
+            Err(err) => tracing::error!(?err, validator_index, "Failed to generate proxy key"),
+        }
+    }
+
+    // The keys are usable either way, they are only generated again on the next start
+    if let Err(err) = write_json(path, &proxy_delegations) {
+        tracing::error!(?err, path, "Failed to save proxy delegations");
+    }
+
+    Ok(proxy_delegations)
+}
+
+/// Split the validators into the saved delegations that can be reused, because they are the
+/// validator's own and the signer still holds their proxy key, and the validators that need
+/// a new proxy key
+pub fn reusable_delegations(
+    mut saved: HashMap<usize, SignedProxyDelegation>,
+    validator_keys: &HashMap<usize, BlsPublicKey>,
+    signer_proxy_keys: &[BlsPublicKey],
+) -> (HashMap<usize, SignedProxyDelegation>, Vec<(usize, BlsPublicKey)>) {
+    let mut reused = HashMap::new();
+    let mut missing = vec![];
+
+    for (validator_index, validator_key) in validator_keys {
+        let reusable = saved.remove(validator_index).filter(|delegation| {
+            delegation.message.delegator == *validator_key && signer_proxy_keys.contains(&delegation.message.proxy)
+        });
+
+        match reusable {
+            Some(delegation) => {
+                reused.insert(*validator_index, delegation);
+            }
+            None => missing.push((*validator_index, *validator_key)),
+        }
+    }
+
+    (reused, missing)
+}
@@ -0,0 +1,175 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+};
+
+use alloy::{
+    primitives::{Address, B256},
+    providers::{Provider, RootProvider},
+    transports::http::Http,
+};
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::{error::InclusionListBoostError, simulation::call_object, types::Transaction};
+
+/// Concurrent `debug_traceCall` requests while tagging candidates
+const MAX_CONCURRENT_TRACES: usize = 16;
+
+/// How a transaction touches a listed address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Exposure {
+    Sender,
+    Recipient,
+    /// Reached through a call made during execution
+    InternalCall,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SanctionsTag {
+    pub address: Address,
+    pub exposure: Exposure,
+}
+
+/// Locally provided list of sanctioned addresses, never fetched over the network
+#[derive(Debug, Default, Clone)]
+pub struct SanctionsList {
+    addresses: HashSet<Address>,
+}
+
+impl SanctionsList {
+    /// Load addresses from a file with one address per line. Blank lines and
+    /// anything after a `#` are ignored
+    pub fn load(path: &str) -> Result<Self, InclusionListBoostError> {
+        let list_str = fs::read_to_string(path)?;
+
+        let addresses = list_str
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                line.parse::<Address>()
+                    .map_err(|err| format!("invalid address {line} in sanctions file {path}: {err}"))
+            })
+            .collect::<Result<HashSet<_>, _>>()?;
+
+        tracing::info!(address_count = addresses.len(), path, "Loaded sanctions list");
+
+        Ok(Self { addresses })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    /// Listed addresses `tx` is sent from or to
+    pub fn direct_tags(&self, tx: &Transaction) -> Vec<SanctionsTag> {
+        let mut tags = vec![];
+
+        if self.addresses.contains(&tx.from) {
+            tags.push(SanctionsTag {
+                address: tx.from,
+                exposure: Exposure::Sender,
+            });
+        }
+
+        if let Some(to) = tx.to.filter(|to| self.addresses.contains(to)) {
+            tags.push(SanctionsTag {
+                address: to,
+                exposure: Exposure::Recipient,
+            });
+        }
+
+        tags
+    }
+
+    /// Tag every candidate touching a listed address. With `trace_calls` candidates that aren't
+    /// tagged directly are traced with `debug_traceCall` and tagged by the calls they make
+    pub async fn tag(
+        &self,
+        eth_provider: &RootProvider<Http<reqwest::Client>>,
+        candidates: &[Transaction],
+        trace_calls: bool,
+    ) -> HashMap<B256, Vec<SanctionsTag>> {
+        let mut tagged = HashMap::new();
+        if self.is_empty() {
+            return tagged;
+        }
+
+        let mut untagged = vec![];
+        for tx in candidates {
+            let tags = self.direct_tags(tx);
+            if tags.is_empty() {
+                untagged.push(tx);
+            } else {
+                tagged.insert(tx.tx_hash, tags);
+            }
+        }
+
+        if trace_calls {
+            let traced = stream::iter(untagged)
+                .map(|tx| async move { (tx.tx_hash, self.traced_tags(eth_provider, tx).await) })
+                .buffer_unordered(MAX_CONCURRENT_TRACES)
+                .collect::<Vec<_>>()
+                .await;
+
+            for (tx_hash, tags) in traced {
+                match tags {
+                    Ok(tags) if !tags.is_empty() => {
+                        tagged.insert(tx_hash, tags);
+                    }
+                    Ok(_) => {}
+                    Err(err) => tracing::debug!(?tx_hash, ?err, "Failed to trace candidate"),
+                }
+            }
+        }
+
+        tagged
+    }
+
+    /// Listed addresses reached by the calls `tx` makes when executed on the head state
+    async fn traced_tags(
+        &self,
+        eth_provider: &RootProvider<Http<reqwest::Client>>,
+        tx: &Transaction,
+    ) -> Result<Vec<SanctionsTag>, InclusionListBoostError> {
+        let trace = eth_provider
+            .raw_request::<_, Value>(
+                "debug_traceCall".into(),
+                (call_object(tx), "latest", json!({ "tracer": "callTracer" })),
+            )
+            .await?;
+
+        let mut reached = HashSet::new();
+        collect_call_addresses(&trace, &mut reached);
+
+        let mut addresses = reached.into_iter().filter(|address| self.addresses.contains(address)).collect::<Vec<_>>();
+        addresses.sort();
+
+        Ok(addresses
+            .into_iter()
+            .map(|address| SanctionsTag {
+                address,
+                exposure: Exposure::InternalCall,
+            })
+            .collect())
+    }
+}
+
+/// Addresses of every nested call frame in a `callTracer` result
+fn collect_call_addresses(frame: &Value, addresses: &mut HashSet<Address>) {
+    let Some(calls) = frame.get("calls").and_then(Value::as_array) else {
+        return;
+    };
+
+    for call in calls {
+        for field in ["from", "to"] {
+            if let Some(address) = call.get(field).and_then(Value::as_str).and_then(|a| a.parse().ok()) {
+                addresses.insert(address);
+            }
+        }
+        collect_call_addresses(call, addresses);
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::HashSet;
+
+use alloy::primitives::B256;
+use serde::Serialize;
+
+use super::types::ListedTransaction;
+
+/// Whether a block satisfied an inclusion list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Satisfaction {
+    Satisfied,
+    /// Conditional lists only: constraints were omitted but none of them fit the remaining gas
+    BlockFull { remaining_gas: u128, smallest_omitted_gas: u128 },
+    Unsatisfied { omitted: Vec<B256> },
+}
+
+impl Satisfaction {
+    pub fn is_satisfied(&self) -> bool {
+        !matches!(self, Satisfaction::Unsatisfied { .. })
+    }
+}
+
+/// Check a block with the `included` transaction hashes and the given gas usage against `listed`.
+/// A conditional list is also satisfied when the block's remaining gas is below the smallest
+/// omitted constraint
+pub fn check_block(
+    listed: &[ListedTransaction],
+    conditional: bool,
+    included: &HashSet<B256>,
+    gas_limit: u128,
+    gas_used: u128,
+) -> Satisfaction {
+    let omitted = listed.iter().filter(|tx| !included.contains(&tx.tx_hash)).collect::<Vec<_>>();
+    let Some(smallest_omitted_gas) = omitted.iter().map(|tx| tx.gas).min() else {
+        return Satisfaction::Satisfied;
+    };
+
+    let remaining_gas = gas_limit.saturating_sub(gas_used);
+    if conditional && remaining_gas < smallest_omitted_gas {
+        return Satisfaction::BlockFull {
+            remaining_gas,
+            smallest_omitted_gas,
+        };
+    }
+
+    Satisfaction::Unsatisfied {
+        omitted: omitted.into_iter().map(|tx| tx.tx_hash).collect(),
+    }
+}
+
+/// Check a header before its payload is known. A conditional list is satisfied whatever the
+/// payload contains if the remaining gas is below every constraint, otherwise only the payload
+/// (or an inclusion proof) can tell and `None` is returned
+pub fn check_header(
+    listed: &[ListedTransaction],
+    conditional: bool,
+    gas_limit: u128,
+    gas_used: u128,
+) -> Option<Satisfaction> {
+    let Some(smallest_gas) = listed.iter().map(|tx| tx.gas).min() else {
+        return Some(Satisfaction::Satisfied);
+    };
+
+    let remaining_gas = gas_limit.saturating_sub(gas_used);
+    (conditional && remaining_gas < smallest_gas).then_some(Satisfaction::BlockFull {
+        remaining_gas,
+        smallest_omitted_gas: smallest_gas,
+    })
+}
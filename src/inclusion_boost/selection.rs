@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+
+use alloy::{primitives::Address, rpc::types::Header};
+use serde::{Deserialize, Serialize};
+
+use super::{fees::DATA_GAS_PER_BLOB, types::Transaction};
+
+/// Skipped blocks dominate the score, then time pending, then the tip.
+/// Touching a sanctioned address counts as much as one skipped block
+const SKIPPED_BLOCK_WEIGHT: u64 = 100_000;
+const SANCTIONS_EXPOSURE_WEIGHT: u64 = SKIPPED_BLOCK_WEIGHT;
+const MAX_SCORED_SECONDS_PENDING: u64 = 60 * 60;
+const MAX_SCORED_TIP_GWEI: u128 = 1_000;
+const GWEI: u128 = 1_000_000_000;
+
+/// Gas budgets are scaled down to at most this many units for the knapsack
+const GAS_UNITS: u128 = 512;
+/// Longest nonce chain per sender considered by the knapsack
+const MAX_CHAIN_LENGTH: usize = u8::MAX as usize;
+
+/// How much execution gas an inclusion list may claim
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GasBudget {
+    /// Gas left unused in the parent block
+    #[default]
+    Remaining,
+    /// Fraction of the parent block's gas limit
+    Fraction(f64),
+    /// Fixed amount of gas, capped at the parent block's gas limit
+    Absolute(u128),
+}
+
+impl GasBudget {
+    pub fn resolve(&self, parent: &Header) -> u128 {
+        match self {
+            GasBudget::Remaining => parent.gas_limit.saturating_sub(parent.gas_used),
+            GasBudget::Fraction(fraction) => (parent.gas_limit as f64 * fraction.clamp(0.0, 1.0)) as u128,
+            GasBudget::Absolute(gas) => (*gas).min(parent.gas_limit),
+        }
+    }
+}
+
+/// Signals that a pending transaction is being censored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CensorshipEvidence {
+    /// Blocks the transaction could have been included in but wasn't
+    pub blocks_skipped: u64,
+    pub seconds_pending: u64,
+    /// Tip per gas the transaction pays at the next block's base fee
+    pub effective_tip: u128,
+    /// The transaction touches an address on the sanctions list
+    pub sanctions_exposed: bool,
+}
+
+impl CensorshipEvidence {
+    /// Every includable transaction scores at least 1
+    pub fn score(&self) -> u64 {
+        let tip = (self.effective_tip / GWEI).min(MAX_SCORED_TIP_GWEI) as u64;
+
+        self.blocks_skipped.saturating_mul(SKIPPED_BLOCK_WEIGHT) +
+            self.sanctions_exposed as u64 * SANCTIONS_EXPOSURE_WEIGHT +
+            self.seconds_pending.min(MAX_SCORED_SECONDS_PENDING) * 10 +
+            tip +
+            1
+    }
+}
+
+/// Tip per gas `tx` pays on top of `base_fee`
+pub fn effective_tip(tx: &Transaction, base_fee: u128) -> u128 {
+    let headroom = tx.max_fee_per_gas.unwrap_or_default().saturating_sub(base_fee);
+    tx.max_priority_fee_per_gas.unwrap_or(headroom).min(headroom)
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate<'a> {
+    pub tx: &'a Transaction,
+    pub evidence: CensorshipEvidence,
+}
+
+/// Pick the candidates that maximize the total censorship score within the gas budgets.
+///
+/// Candidates must be in nonce order per sender, a transaction is only picked together with
+/// all of its sender's earlier transactions. The result only depends on the set of candidates,
+/// not on the order senders appear in
+pub fn select<'a>(
+    candidates: Vec<Candidate<'a>>,
+    gas_budget: u128,
+    blob_gas_budget: u128,
+) -> Vec<Candidate<'a>> {
+    let mut chains = group_chains(candidates);
+
+    let total_gas: u128 = chains.iter().flatten().map(|c| c.tx.gas).sum();
+    let total_blob_gas: u128 = chains.iter().flatten().map(|c| c.tx.blob_gas()).sum();
+    if total_gas <= gas_budget && total_blob_gas <= blob_gas_budget {
+        return chains.into_iter().flatten().collect();
+    }
+
+    let unit = gas_budget.div_ceil(GAS_UNITS).max(1);
+    let capacity = (gas_budget / unit) as usize;
+    let blob_capacity = (blob_gas_budget / DATA_GAS_PER_BLOB) as usize;
+    let cell = |gas: usize, blobs: usize| gas * (blob_capacity + 1) + blobs;
+    let cells = (capacity + 1) * (blob_capacity + 1);
+
+    // best[cell] is the best score using at most that much gas and blobs,
+    // choices[chain][cell] the prefix length of the chain picked to reach it
+    let mut best = vec![0u64; cells];
+    let mut choices = vec![vec![0u8; cells]; chains.len()];
+
+    for (chain_index, chain) in chains.iter().enumerate() {
+        let mut prefix = Vec::with_capacity(chain.len());
+        let (mut gas, mut blobs, mut score) = (0usize, 0usize, 0u64);
+        for candidate in chain {
+            gas += candidate.tx.gas.div_ceil(unit) as usize;
+            blobs += (candidate.tx.blob_gas() / DATA_GAS_PER_BLOB) as usize;
+            score += candidate.evidence.score();
+            prefix.push((gas, blobs, score));
+        }
+
+        let previous = best.clone();
+        for g in 0..=capacity {
+            for b in 0..=blob_capacity {
+                for (length, (gas, blobs, score)) in prefix.iter().enumerate() {
+                    if *gas > g || *blobs > b {
+                        break;
+                    }
+                    let value = previous[cell(g - gas, b - blobs)] + score;
+                    if value > best[cell(g, b)] {
+                        best[cell(g, b)] = value;
+                        choices[chain_index][cell(g, b)] = (length + 1) as u8;
+                    }
+                }
+            }
+        }
+    }
+
+    // Walk the choices back from the full budget
+    let (mut g, mut b) = (capacity, blob_capacity);
+    let mut selected = vec![];
+    for (chain_index, chain) in chains.iter_mut().enumerate().rev() {
+        let length = choices[chain_index][cell(g, b)] as usize;
+        if length == 0 {
+            continue;
+        }
+
+        let picked = chain.drain(..length).collect::<Vec<_>>();
+        g -= picked.iter().map(|c| c.tx.gas.div_ceil(unit) as usize).sum::<usize>();
+        b -= picked.iter().map(|c| (c.tx.blob_gas() / DATA_GAS_PER_BLOB) as usize).sum::<usize>();
+        selected.push(picked);
+    }
+
+    selected.into_iter().rev().flatten().collect()
+}
+
+/// Split candidates into per sender nonce chains, ordered by sender address
+fn group_chains(candidates: Vec<Candidate<'_>>) -> Vec<Vec<Candidate<'_>>> {
+    let mut chains: BTreeMap<Address, Vec<Candidate<'_>>> = BTreeMap::new();
+
+    for candidate in candidates {
+        let chain = chains.entry(candidate.tx.from).or_default();
+        if chain.len() < MAX_CHAIN_LENGTH {
+            chain.push(candidate);
+        }
+    }
+
+    chains.into_values().collect()
+}
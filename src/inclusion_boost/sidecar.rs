@@ -1,76 +1,223 @@
-use std::{collections::HashMap, sync::Arc, thread::sleep, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use alloy::{
-    eips::BlockId,
-    providers::{ext::TxPoolApi, Provider, RootProvider},
-    rpc::types::{beacon::events::HeadEvent, Block, BlockTransactionsKind},
+    eips::{BlockId, BlockNumberOrTag},
+    primitives::{Bytes, B256},
+    providers::{ext::TxPoolApi, Provider, ProviderBuilder, RootProvider},
+    rpc::types::{
+        beacon::{events::HeadEvent, BlsPublicKey},
+        Block, BlockTransactionsKind, Header,
+    },
     transports::http::Http,
 };
 
+use beacon_api_client::ProposerDuty;
 use cb_common::config::StartCommitModuleConfig;
-use futures::StreamExt;
+use futures::{future::join_all, StreamExt};
 use mev_share_sse::EventClient;
 
 use crate::{
-    config::InclusionListConfig, inclusion_boost::types::InclusionList,
+    config::InclusionListConfig,
+    inclusion_boost::types::{InclusionList, InclusionListFormat, InclusionListMode, InclusionListType},
     lookahead::{error::LookaheadError, LookaheadProvider},
+    status::{
+        DelegationReport, InclusionListReport, ModuleStatus, SatisfactionReport, SubmissionResult,
+        UpcomingProposal,
+    },
 };
 
 use super::{
+    aggregation::{Committee, LOCAL_VANTAGE_POINT},
+    clock::{Genesis, SlotClock, SECONDS_PER_SLOT, SLOTS_PER_EPOCH},
+    delegation::Delegation,
     error::InclusionListBoostError,
-    types::{InclusionBoostCache, Transaction},
+    fees::{BlobParams, NextBlockFees},
+    focil::{self, DomainInputs, FocilInclusionList},
+    history::BlockRecord,
+    mempool::{MempoolSources, POLL_TIMEOUT},
+    nonces::{lookup_nonces, order_with_nonces, AccountNonces},
+    policy::InclusionPolicy,
+    proxy,
+    sanctions::{SanctionsList, SanctionsTag},
+    satisfaction::check_block,
+    simulation::Simulator,
+    types::{
+        CachedInclusionList, DroppedCandidate, InclusionBoostCache, ListKey, ListedTransaction, SlotOverrides,
+        Transaction,
+    },
     InclusionBoost,
 };
 
+/// How many slots of inclusion lists and overrides to keep around
+const CACHE_RETENTION_SLOTS: u64 = 64;
+
+/// Timeout of the requests to the beacon node and the relay data API
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How far back past missed slots to look for the block to build on
+pub const MAX_MISSED_SLOTS: u64 = 32;
+
+/// An inclusion list to publish: the slot it constrains and the proposer signing it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ListTarget {
+    pub slot: u64,
+    pub validator_index: usize,
+    pub il_type: InclusionListType,
+    pub revision: u64,
+}
+
+impl ListTarget {
+    fn key(&self) -> ListKey {
+        (self.slot, self.il_type)
+    }
+
+    /// Slot of the proposer signing the list, whose `get_header` call the list must precede
+    fn proposer_slot(&self) -> u64 {
+        match self.il_type {
+            InclusionListType::Spot => self.slot,
+            InclusionListType::Forward => self.slot - 1,
+        }
+    }
+}
+
 pub struct InclusionSideCar {
     inclusion_boost: InclusionBoost,
     eth_provider: RootProvider<Http<reqwest::Client>>,
     cache: Arc<InclusionBoostCache>,
     il_config: InclusionListConfig,
+    status: Arc<ModuleStatus>,
+    policy: InclusionPolicy,
+    sanctions: Option<SanctionsList>,
+    /// Named providers of the other execution nodes lists are built from
+    vantage_points: Vec<(String, RootProvider<Http<reqwest::Client>>)>,
+    mempool: Arc<MempoolSources>,
+    /// Proxy key inclusion list authority is delegated to, once the signer confirmed it
+    delegate_key: Option<BlsPublicKey>,
+    /// Known once the beacon node's genesis was fetched
+    slot_clock: Option<SlotClock>,
+    /// Fetched at startup when publishing EIP-7805 lists
+    domain_inputs: Option<DomainInputs>,
+    /// Revisions still due for the lists last published, until the next head
+    revisions: Option<RevisionSchedule>,
+    /// Client for beacon node and relay data API requests
+    http_client: reqwest::Client,
 }
 
 impl InclusionSideCar {
     pub fn new(
         config: StartCommitModuleConfig<InclusionListConfig>,
-        eth_provider: RootProvider<alloy::transports::http::Http<reqwest::Client>>,
+        mempool: Arc<MempoolSources>,
         cache: Arc<InclusionBoostCache>,
-    ) -> Self {        
-        let inclusion_boost = InclusionBoost::new(
+        status: Arc<ModuleStatus>,
+        policy: InclusionPolicy,
+        sanctions: Option<SanctionsList>,
+    ) -> Self {
+        let mut inclusion_boost = InclusionBoost::new(
             config.id.to_string(),
             config.signer_client,
             HashMap::new(),
             config.extra.clone().relay, // TODO get from config
         );
+        inclusion_boost.protection = cache.protection.clone();
+
+        let vantage_points = config
+            .extra
+            .vantage_points
+            .iter()
+            .map(|vantage_point| {
+                let url = vantage_point.execution_api.parse().expect("invalid vantage point execution_api");
+                (vantage_point.name.clone(), ProviderBuilder::new().on_http(url))
+            })
+            .collect();
 
         Self {
             inclusion_boost,
-            eth_provider,
+            eth_provider: mempool.provider().clone(),
             cache,
             il_config: config.extra,
+            status,
+            policy,
+            sanctions,
+            vantage_points,
+            mempool,
+            delegate_key: None,
+            slot_clock: None,
+            domain_inputs: None,
+            revisions: None,
+            http_client: reqwest::Client::builder().timeout(HTTP_TIMEOUT).build().expect("failed to build http client"),
         }
     }
 
     pub async fn run(&mut self) -> Result<(), InclusionListBoostError> {
         let lookahead_provider = LookaheadProvider::new(&self.il_config.beacon_api);
-        sleep(Duration::from_secs(60));
         let pubkeys = self.inclusion_boost.signer_client.get_pubkeys().await?;
 
         for p in pubkeys.consensus {
             let index = get_validator_index(&self.il_config.beacon_api, &p.to_string()).await?;
             if let Some(validator_index) = index {
-                println!("validator_index {}", validator_index);
+                tracing::info!(validator_index, "Loaded validator key");
                 self.inclusion_boost.validator_keys.insert(validator_index as usize, p);
             }
         }
 
-        let mut lookahead = lookahead_provider.get_current_lookahead().await?;
-        let mut next_lookahead = lookahead_provider.get_next_epoch_lookahead().await?;
+        self.delegate_key = match self.il_config.delegate_pubkey {
+            Some(delegate_pubkey) if pubkeys.proxy.contains(&delegate_pubkey) => {
+                tracing::info!(%delegate_pubkey, "Loaded delegate key");
+                Some(delegate_pubkey)
+            }
+            Some(delegate_pubkey) => {
+                tracing::error!(%delegate_pubkey, "Delegate key is not a proxy key of the signer, validator keys sign the lists");
+                None
+            }
+            None => None,
+        };
+
+        let proxy_delegations_file = self.il_config.proxy_delegations_file.as_deref();
+        if let Some(path) = proxy_delegations_file.filter(|_| self.il_config.use_proxy_keys) {
+            self.inclusion_boost.proxy_delegations = proxy::load_proxy_keys(
+                &self.inclusion_boost.signer_client,
+                &self.inclusion_boost.validator_keys,
+                &pubkeys.proxy,
+                path,
+            )
+            .await?;
+            tracing::info!(proxy_keys = self.inclusion_boost.proxy_delegations.len(), "Loaded proxy keys");
+        }
+
+        let genesis = Genesis::fetch(&self.http_client, &self.il_config.beacon_api).await;
+        let slot_clock = match &genesis {
+            Ok(genesis) => SlotClock::fetch(&self.http_client, &self.il_config.beacon_api, genesis).await,
+            Err(err) => Err(format!("failed to fetch genesis: {err:?}").into()),
+        };
+        self.slot_clock = match slot_clock {
+            Ok(slot_clock) => Some(slot_clock),
+            Err(err) => {
+                tracing::error!(?err, "Failed to fetch the slot clock, lists are built on head events and won't be revised");
+                None
+            }
+        };
 
-        for future_proposer in next_lookahead {
-            let res = self.inclusion_boost.delegate_inclusion_list_authority(future_proposer.validator_index, future_proposer.slot).await;
-            println!("{:?}", res);
+        // Without the signing domain no EIP-7805 list can be published, so startup is retried
+        if self.il_config.inclusion_list_format == InclusionListFormat::Focil {
+            let genesis = genesis?;
+            let slot_clock = self.slot_clock.ok_or_else(|| "the signing domain needs the slot clock".to_string())?;
+            let domain_inputs =
+                DomainInputs::fetch(&self.http_client, &self.il_config.beacon_api, &genesis, &slot_clock).await?;
+            self.domain_inputs = Some(domain_inputs);
         }
-       
+
+        let validator_keys = self.inclusion_boost.validator_keys.len();
+        self.status.update(|status| status.validator_keys = validator_keys);
+
+        let mut lookahead = lookahead_provider.get_current_lookahead().await?;
+        self.record_upcoming_proposals(&lookahead);
+        let from_slot = lookahead.first().map(|duty| duty.slot).unwrap_or_default();
+        self.reconcile_delegations(&lookahead, &lookahead_provider, from_slot).await;
+
         let lookahead_size = lookahead.len();
         tracing::info!(lookahead_size, "Initial proposer lookahead fetched");
 
@@ -78,125 +225,983 @@ impl InclusionSideCar {
         let target = format!("{}/eth/v1/events?topics=head", self.il_config.beacon_api);
         let mut sub = event_client.subscribe::<HeadEvent>(&target).await?;
 
-        while let Some(head_event) = sub.next().await {
-            let head_event = head_event?;
+        // Slot whose scheduled build comes next, and the unix time in milliseconds it is due
+        let mut next_build =
+            self.build_schedule().map(|(slot_clock, offset)| slot_clock.next_slot_offset(unix_millis(), offset));
+
+        loop {
+            let build_delay = next_build.map(|(_, due)| due.saturating_sub(unix_millis())).unwrap_or_default();
+            let revision_delay =
+                self.revisions.as_ref().map(|revisions| revisions.due.saturating_sub(unix_millis())).unwrap_or_default();
+
+            tokio::select! {
+                head_event = sub.next() => {
+                    let Some(head_event) = head_event else {
+                        break;
+                    };
+                    let head_event = match head_event {
+                        Ok(head_event) => head_event,
+                        Err(err) => {
+                            tracing::error!(?err, "Failed to read head event");
+                            continue;
+                        }
+                    };
 
-            if head_event.epoch_transition {
-                lookahead = lookahead_provider.get_current_lookahead().await?;
-                next_lookahead = lookahead_provider.get_next_epoch_lookahead().await?;
-                for future_proposer in next_lookahead {
-                    let res = self.inclusion_boost.delegate_inclusion_list_authority(future_proposer.validator_index, future_proposer.slot).await;
-                    println!("{:?}", res);
+                    if let Err(err) = self
+                        .process_head_event(&head_event, &lookahead_provider, &mut lookahead)
+                        .await
+                    {
+                        self.status.health.record_slot_failure(head_event.slot, &err);
+                    }
+
+                    self.status.update(|status| status.last_head_slot = Some(head_event.slot));
+                }
+                _ = tokio::time::sleep(Duration::from_millis(build_delay)), if next_build.is_some() => {
+                    let Some((slot, _)) = next_build else {
+                        continue;
+                    };
+
+                    if let Err(err) = self.process_scheduled_slot(slot, &lookahead_provider, &lookahead).await {
+                        self.status.health.record_slot_failure(slot, &err);
+                    }
+
+                    // A build that overran the next slot's offset skips that slot rather than running late
+                    next_build = self.build_schedule().map(|(slot_clock, offset)| {
+                        let next = slot_clock.next_slot_offset(unix_millis(), offset);
+                        next.max((slot + 1, slot_clock.slot_start_millis(slot + 1) + offset))
+                    });
+                }
+                _ = tokio::time::sleep(Duration::from_millis(revision_delay)), if self.revisions.is_some() => {
+                    let Some(revisions) = self.revisions.take() else {
+                        continue;
+                    };
+
+                    self.publish_revision(&revisions).await;
+                    self.revisions = revisions.advance(unix_millis());
                 }
-                tracing::info!("Epoch transition, fetched new proposer lookahead...");
             }
+        }
 
-            // Get the next slots proposer
-            let Some(next_proposer) = lookahead
-                .iter()
-                .find(|duty| duty.slot == &head_event.slot + 1)
-            else {
-                tracing::info!("At end of epoch, waiting");
-                continue;
-            };
+        Ok(())
+    }
+
+    /// The slot clock and the offset into the slot lists are built at, when they are built on
+    /// the clock rather than on head events
+    fn build_schedule(&self) -> Option<(SlotClock, u64)> {
+        self.slot_clock.zip(self.il_config.build_offset_ms)
+    }
 
-            // TODO check if next slots proposer is ours
-            let block_number = self.get_block_number_by_slot(head_event.slot - 1).await?;
+    /// Does all the work for a single head event. Any error returned here only affects
+    /// this slot, the caller logs it and moves on to the next head event
+    async fn process_head_event(
+        &mut self,
+        head_event: &HeadEvent,
+        lookahead_provider: &LookaheadProvider,
+        lookahead: &mut Vec<ProposerDuty>,
+    ) -> Result<(), InclusionListBoostError> {
+        // Revisions were built on the previous head, new lists are built on this one
+        self.revisions = None;
+        self.cache.prune(head_event.slot.saturating_sub(CACHE_RETENTION_SLOTS));
 
-            let Some(block_number) = block_number else {
-                continue;
-            };
+        // Fall back to another execution node when the current one failed its last mempool poll
+        self.eth_provider = self.mempool.provider().clone();
 
+        // Keep the censorship window current on every head, not only before our own proposals
+        if let Err(err) = self.update_block_history().await {
+            tracing::warn!(?err, "Failed to update the block history");
+        }
 
-            let Some(latest_block) = self.get_block_by_number(block_number).await? else {
-                continue;
-            };
+        if let Err(err) = self.check_satisfaction(head_event.slot).await {
+            tracing::warn!(?err, slot = head_event.slot, "Failed to check inclusion list satisfaction");
+        }
 
-            tracing::info!(
+        // Operator revocations are sent on the next head, reconciling waits for the epoch transition
+        self.revoke_pending_delegations().await;
+
+        if head_event.epoch_transition {
+            *lookahead = lookahead_provider.get_current_lookahead().await?;
+            self.record_upcoming_proposals(lookahead);
+            self.reconcile_delegations(lookahead, lookahead_provider, head_event.slot + 1).await;
+            tracing::info!("Epoch transition, fetched new proposer lookahead...");
+        }
+
+        // The slot clock builds the lists instead
+        if self.build_schedule().is_some() {
+            return Ok(());
+        }
+
+        let targets = self.list_targets(head_event.slot, lookahead_provider, lookahead).await;
+        if targets.is_empty() {
+            return Ok(());
+        }
+
+        let Some(block_number) =
+            resolve_head_block_number(&self.http_client, &self.il_config.beacon_api, head_event.slot, head_event.block)
+                .await?
+        else {
+            tracing::warn!(slot = head_event.slot, "No block found to build on");
+            return Ok(());
+        };
+
+        let Some(latest_block) = self.get_block_by_number(block_number).await? else {
+            return Ok(());
+        };
+
+        tracing::info!(
+            block_number = latest_block.header.number,
+            transaction_count = latest_block.transactions.len(),
+            current_slot = head_event.slot,
+            "Fetched latest block"
+        );
+
+        self.publish_targets(&latest_block, &targets).await
+    }
+
+    /// Build the lists for the proposal after `slot` at the configured offset into `slot`,
+    /// whether or not its block arrived. A missed or late block leaves the latest known block
+    /// to build on
+    async fn process_scheduled_slot(
+        &mut self,
+        slot: u64,
+        lookahead_provider: &LookaheadProvider,
+        lookahead: &[ProposerDuty],
+    ) -> Result<(), InclusionListBoostError> {
+        // The epoch transition head event may not have arrived yet when the proposal starts an epoch
+        let mut duties = lookahead.to_vec();
+        if !duties.iter().any(|duty| duty.slot == slot + 1) {
+            duties.extend(lookahead_provider.get_next_epoch_lookahead().await?);
+        }
+
+        let targets = self.list_targets(slot, lookahead_provider, &duties).await;
+        if targets.is_empty() {
+            return Ok(());
+        }
+
+        let Some(latest_block) = self.eth_provider.get_block_by_number(BlockNumberOrTag::Latest, true).await? else {
+            return Ok(());
+        };
+
+        let slot_start =
+            self.slot_clock.map(|slot_clock| slot_clock.slot_start_millis(slot) / 1000).unwrap_or_default();
+        if latest_block.header.timestamp < slot_start {
+            tracing::warn!(
+                slot,
                 block_number = latest_block.header.number,
-                transaction_count = latest_block.transactions.len(),
-                current_slot = head_event.slot,
-                "Fetched latest block"
+                block_timestamp = latest_block.header.timestamp,
+                "Block of the slot hasn't arrived, building on the latest known block"
             );
+        }
 
-            // TODO we'll probably want to cache the inclusion list so we can validate merkle proofs later
+        tracing::info!(
+            block_number = latest_block.header.number,
+            transaction_count = latest_block.transactions.len(),
+            current_slot = slot,
+            "Building scheduled inclusion lists"
+        );
 
-            let Some(inclusion_list) = self
-                .build_inclusion_list(
-                    &latest_block,
-                    next_proposer.slot,
-                    next_proposer.validator_index,
-                )
-                .await?
-            else {
-                continue;
+        self.publish_targets(&latest_block, &targets).await
+    }
+
+    /// Publish the lists of `targets` on top of `latest_block` and schedule their revisions.
+    /// Every target gets its list even if an earlier one failed, the first error is returned
+    async fn publish_targets(
+        &mut self,
+        latest_block: &Block,
+        targets: &[ListTarget],
+    ) -> Result<(), InclusionListBoostError> {
+        let mut result = Ok(());
+        for target in targets {
+            let submission = self.publish_inclusion_list(latest_block, *target).await;
+            if result.is_ok() {
+                result = submission;
+            }
+        }
+
+        self.revisions = self.revision_schedule(latest_block, targets);
+
+        result
+    }
+
+    /// Lists of `targets` are rebuilt every `revision_interval_ms` as new evidence arrives and
+    /// the ones that changed are resubmitted with a higher revision, until `revision_cutoff_ms`
+    /// before the proposer of the first target calls `get_header` at the start of its slot.
+    /// EIP-7805 lists aren't revised, a second list would be an equivocation
+    fn revision_schedule(&self, latest_block: &Block, targets: &[ListTarget]) -> Option<RevisionSchedule> {
+        let interval = self.il_config.revision_interval_ms;
+        if self.il_config.inclusion_list_format == InclusionListFormat::Focil {
+            return None;
+        }
+
+        let slot_clock = self.slot_clock?;
+        let first_slot = targets.iter().map(|target| target.slot).min()?;
+        let cutoff = slot_clock.slot_start_millis(first_slot).saturating_sub(self.il_config.revision_cutoff_ms);
+
+        RevisionSchedule::new(latest_block.clone(), targets.to_vec(), unix_millis(), cutoff, interval)
+    }
+
+    /// Rebuild and resubmit the lists of the revision that is due
+    async fn publish_revision(&self, revisions: &RevisionSchedule) {
+        // Publishing the previous revision may have taken past the cutoff
+        if unix_millis() >= revisions.cutoff {
+            return;
+        }
+
+        for target in &revisions.targets {
+            let target = ListTarget {
+                revision: revisions.revision,
+                ..*target
             };
+            if let Err(err) = self.publish_inclusion_list(&revisions.latest_block, target).await {
+                tracing::warn!(?err, slot = target.slot, revision = target.revision, "Failed to publish inclusion list revision");
+            }
+        }
+    }
+
+    /// The lists to publish after the head moved to `head_slot`, see `select_list_targets`.
+    /// The next epoch's lookahead is fetched when the forward slot starts it
+    async fn list_targets(
+        &self,
+        head_slot: u64,
+        lookahead_provider: &LookaheadProvider,
+        lookahead: &[ProposerDuty],
+    ) -> Vec<ListTarget> {
+        let mut duties = lookahead.to_vec();
+
+        let signs_next = lookahead.iter().any(|duty| {
+            duty.slot == head_slot + 1 && self.inclusion_boost.validator_keys.contains_key(&duty.validator_index)
+        });
+        let forward_known = lookahead.iter().any(|duty| duty.slot == head_slot + 2);
+        if signs_next && !forward_known && self.il_config.inclusion_list_mode.publishes(InclusionListType::Forward) {
+            match lookahead_provider.get_next_epoch_lookahead().await {
+                Ok(next_lookahead) => duties.extend(next_lookahead),
+                Err(err) => tracing::error!(?err, "Failed to fetch next epoch lookahead"),
+            }
+        }
+
+        select_list_targets(
+            self.il_config.inclusion_list_mode,
+            head_slot,
+            |slot| duties.iter().find(|duty| duty.slot == slot).map(|duty| duty.validator_index),
+            |validator_index| self.inclusion_boost.validator_keys.contains_key(&validator_index),
+            &self.il_config.forward_opted_in_validators,
+        )
+    }
+
+    /// Build, sign and submit the list for `target`, recording the outcome in the status
+    async fn publish_inclusion_list(
+        &self,
+        latest_block: &Block,
+        target: ListTarget,
+    ) -> Result<(), InclusionListBoostError> {
+        let Some(inclusion_list) = self.build_inclusion_list(latest_block, target).await? else {
+            return Ok(());
+        };
+
+        // Revisions are only submitted when the list changed since the last accepted one, which
+        // the cached list then keeps the revision of
+        if target.revision > 0 {
+            let submitted = self.cache.submitted.read().get(&target.key()).cloned();
+            if let Some(submitted) = submitted.filter(|submitted| inclusion_list.unchanged_from(submitted)) {
+                if let Some(cached) = self.cache.inclusion_list_cache.write().get_mut(&target.key()) {
+                    cached.inclusion_list.revision = submitted.revision;
+                }
+                return Ok(());
+            }
+        }
+
+        // A list arriving after the proposer called `get_header` can't constrain its block
+        if let Some(deadline) = self.submission_deadline(target) {
+            let now = unix_millis();
+            if now > deadline {
+                return Err(format!(
+                    "missed the submission deadline of slot {} by {}ms",
+                    target.slot,
+                    now - deadline
+                )
+                .into());
+            }
+        }
+
+        let (simulated_gas_used, censorship_evidence, sources, raw_transactions) = self
+            .cache
+            .inclusion_list_cache
+            .read()
+            .get(&target.key())
+            .map(|cached| {
+                let raw_transactions = cached.transactions.iter().filter_map(|tx| tx.raw.clone()).collect::<Vec<_>>();
+                (cached.simulated_gas_used, cached.evidence.clone(), cached.sources.clone(), raw_transactions)
+            })
+            .unwrap_or_default();
+
+        let (constraints, submission) = match self.il_config.inclusion_list_format {
+            InclusionListFormat::Constraints => {
+                let constraints = inclusion_list.constraints.iter().map(|c| c.len()).sum();
+                let submitted = inclusion_list.clone();
+                let submission = match self.delegated_signer(target) {
+                    Some(delegate_key) => {
+                        self.inclusion_boost.submit_delegated_inclusion_list(delegate_key, inclusion_list).await
+                    }
+                    None => {
+                        self.inclusion_boost
+                            .submit_inclusion_list_to_relay(target.validator_index, inclusion_list)
+                            .await
+                    }
+                };
+                if let Ok(Some(())) = submission {
+                    self.cache.submitted.write().insert(target.key(), submitted);
+                }
+                (constraints, submission)
+            }
+            InclusionListFormat::Focil => match &self.domain_inputs {
+                Some(domain_inputs) => {
+                    let list = self.focil_inclusion_list(target, domain_inputs, &raw_transactions).await;
+                    let constraints = list.transactions.len();
+                    let submission =
+                        self.inclusion_boost.submit_focil_inclusion_list(list, domain_inputs.domain(target.slot)).await;
+                    (constraints, submission)
+                }
+                None => (0, Err("signing domain is unknown".to_string().into())),
+            },
+        };
+        let result = match &submission {
+            Ok(Some(())) => SubmissionResult::Accepted,
+            Ok(None) => SubmissionResult::Rejected,
+            Err(err) => SubmissionResult::Failed {
+                error: format!("{err:?}"),
+            },
+        };
+
+        let report = InclusionListReport {
+            slot: target.slot,
+            validator_index: target.validator_index,
+            inclusion_list_type: target.il_type,
+            revision: target.revision,
+            constraints,
+            simulated_gas_used,
+            censorship_evidence,
+            sources,
+            submissions: HashMap::from([(self.inclusion_boost.relay_url.clone(), result)]),
+        };
+        self.status.update(|status| status.last_inclusion_list = Some(report));
+
+        submission.map(|_| ())
+    }
+
+    /// Unix time in milliseconds after which the list for `target` isn't submitted anymore
+    fn submission_deadline(&self, target: ListTarget) -> Option<u64> {
+        let deadline_ms = self.il_config.submission_deadline_ms;
+        let slot_clock = self.slot_clock.filter(|_| deadline_ms > 0)?;
+        Some(slot_clock.slot_start_millis(target.proposer_slot()).saturating_sub(deadline_ms))
+    }
+
+    /// The delegate key signs a list when the relay accepted the delegation of the signing
+    /// proposer's slot to it
+    fn delegated_signer(&self, target: ListTarget) -> Option<BlsPublicKey> {
+        let delegate_key = self.delegate_key?;
+
+        self.cache
+            .delegations
+            .read()
+            .delegate_of(target.proposer_slot(), &self.inclusion_boost.relay_url)
+            .filter(|delegated| *delegated == delegate_key)
+    }
+
+    /// EIP-7805 list for `target` with the listed transactions that have a raw encoding
+    async fn focil_inclusion_list(
+        &self,
+        target: ListTarget,
+        domain_inputs: &DomainInputs,
+        raw_transactions: &[Bytes],
+    ) -> FocilInclusionList {
+        let validator_index = target.validator_index as u64;
+        let committee_root = match focil::fetch_committee_root(
+            &self.http_client,
+            &self.il_config.beacon_api,
+            domain_inputs.epoch(target.slot),
+            target.slot,
+            validator_index,
+        )
+        .await
+        {
+            Ok(Some(root)) => root,
+            Ok(None) => {
+                tracing::warn!(slot = target.slot, "No inclusion list committee duty, using an empty committee root");
+                B256::ZERO
+            }
+            Err(err) => {
+                tracing::warn!(?err, slot = target.slot, "Failed to fetch the inclusion list committee root");
+                B256::ZERO
+            }
+        };
+
+        let list = FocilInclusionList::new(target.slot, validator_index, committee_root, raw_transactions);
+        tracing::info!(
+            slot = target.slot,
+            transaction_count = list.transactions.len(),
+            skipped_count = raw_transactions.len() - list.transactions.len(),
+            "Built EIP-7805 inclusion list"
+        );
+
+        list
+    }
+
+    /// Check the block of `slot` against the spot and forward lists we published for it, if any
+    async fn check_satisfaction(&self, slot: u64) -> Result<(), InclusionListBoostError> {
+        let lists = {
+            let cache = self.cache.inclusion_list_cache.read();
+            [InclusionListType::Spot, InclusionListType::Forward]
+                .into_iter()
+                .filter_map(|il_type| {
+                    let cached = cache.get(&(slot, il_type))?;
+                    Some((il_type, cached.transactions.clone(), cached.inclusion_list.conditional))
+                })
+                .collect::<Vec<_>>()
+        };
+        if lists.is_empty() {
+            return Ok(());
+        }
+
+        let Some(block_number) = get_block_number_by_slot(&self.http_client, &self.il_config.beacon_api, slot).await?
+        else {
+            return Ok(());
+        };
+        let Some(block) = self.get_block_by_number(block_number).await? else {
+            return Ok(());
+        };
+
+        let included = block.transactions.txns().map(|tx| tx.hash).collect::<HashSet<_>>();
+        for (il_type, listed, conditional) in lists {
+            let satisfaction =
+                check_block(&listed, conditional, &included, block.header.gas_limit, block.header.gas_used);
+
+            tracing::info!(
+                slot,
+                ?il_type,
+                block_number,
+                conditional,
+                ?satisfaction,
+                "Checked inclusion list satisfaction"
+            );
+
+            if let Some(cached) = self.cache.inclusion_list_cache.write().get_mut(&(slot, il_type)) {
+                cached.satisfaction = Some(satisfaction.clone());
+            }
+            self.status.update(|status| {
+                status.last_satisfaction = Some(SatisfactionReport {
+                    slot,
+                    inclusion_list_type: il_type,
+                    conditional,
+                    satisfaction,
+                })
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Publish the slots in `lookahead` that one of our keys will propose
+    fn record_upcoming_proposals(&self, lookahead: &[ProposerDuty]) {
+        let upcoming_proposals = lookahead
+            .iter()
+            .filter(|duty| self.inclusion_boost.validator_keys.contains_key(&duty.validator_index))
+            .map(|duty| UpcomingProposal {
+                slot: duty.slot,
+                validator_index: duty.validator_index,
+            })
+            .collect();
+
+        self.status.update(|status| status.upcoming_proposals = upcoming_proposals);
+    }
+
+    /// Delegate inclusion list authority for our proposals from `from_slot` on, in the current
+    /// and the next epoch. Delegations already sent are skipped, the ones that no longer match
+    /// the configuration are revoked. Failures are logged per proposer and never abort the caller
+    async fn reconcile_delegations(
+        &self,
+        lookahead: &[ProposerDuty],
+        lookahead_provider: &LookaheadProvider,
+        from_slot: u64,
+    ) {
+        let next_lookahead = match lookahead_provider.get_next_epoch_lookahead().await {
+            Ok(next_lookahead) => next_lookahead,
+            Err(err) => {
+                tracing::error!(?err, "Failed to fetch next epoch lookahead");
+                return;
+            }
+        };
 
-            self.inclusion_boost
-                .submit_inclusion_list_to_relay(next_proposer.validator_index, inclusion_list)
-                .await?;
+        let desired = lookahead
+            .iter()
+            .chain(&next_lookahead)
+            .filter(|duty| self.il_config.delegate_inclusion_lists && duty.slot >= from_slot)
+            .filter_map(|duty| {
+                let validator_pubkey = self.inclusion_boost.validator_keys.get(&duty.validator_index)?;
+                Some(Delegation {
+                    slot: duty.slot,
+                    validator_index: duty.validator_index,
+                    validator_pubkey: validator_pubkey.clone(),
+                    delegate_pubkey: self.delegate_key.unwrap_or(*validator_pubkey),
+                    relay: self.inclusion_boost.relay_url.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let desired_count = desired.len();
+        let to_delegate = self.cache.delegations.write().plan(desired, from_slot);
+
+        let mut report = DelegationReport {
+            epoch: next_lookahead
+                .first()
+                .map(|duty| duty.slot / self.slot_clock.map_or(SLOTS_PER_EPOCH, |clock| clock.slots_per_epoch()))
+                .unwrap_or_default(),
+            already_delegated: desired_count - to_delegate.len(),
+            ..Default::default()
+        };
+
+        report.revoked = self.revoke_pending_delegations().await;
+
+        for delegation in to_delegate {
+            match self.inclusion_boost.delegate_inclusion_list_authority(&delegation).await {
+                Ok(Some(())) => {
+                    report.succeeded.push(delegation.slot);
+                    self.cache.delegations.write().record_delegated(delegation);
+                }
+                Ok(None) => report.failed.push(delegation.slot),
+                Err(err) => {
+                    tracing::error!(
+                        ?err,
+                        validator_index = delegation.validator_index,
+                        slot = delegation.slot,
+                        "Failed to delegate inclusion list authority"
+                    );
+                    report.failed.push(delegation.slot);
+                }
+            }
+        }
+
+        tracing::info!(
+            epoch = report.epoch,
+            succeeded = report.succeeded.len(),
+            failed = report.failed.len(),
+            already_delegated = report.already_delegated,
+            revoked = report.revoked.len(),
+            "Reconciled inclusion list delegations"
+        );
+        self.status.update(|status| status.next_epoch_delegations = Some(report));
+    }
+
+    /// Send the queued revocations, the ones the relay doesn't accept stay queued.
+    /// Returns the slots that were revoked
+    async fn revoke_pending_delegations(&self) -> Vec<u64> {
+        let pending = self.cache.delegations.read().pending_revocations();
+
+        let mut revoked = vec![];
+        for delegation in pending {
+            match self.inclusion_boost.revoke_inclusion_list_authority(&delegation).await {
+                Ok(Some(())) => {
+                    revoked.push(delegation.slot);
+                    self.cache.delegations.write().record_revoked(&delegation);
+                }
+                Ok(None) => tracing::warn!(slot = delegation.slot, "Relay rejected the revocation"),
+                Err(err) => tracing::error!(?err, slot = delegation.slot, "Failed to revoke delegation"),
+            }
+        }
+
+        revoked
+    }
+
+    /// Record the latest block and backfill the rest of the censorship window, then note which
+    /// transactions are pending so that later blocks skipping them count against them.
+    /// New blocks are attributed to their builder and charged with the transactions they skipped
+    async fn update_block_history(&self) -> Result<(), InclusionListBoostError> {
+        let Some(latest_block) = self.eth_provider.get_block_by_number(BlockNumberOrTag::Latest, true).await? else {
+            return Ok(());
+        };
+
+        let latest_number = latest_block.header.number.unwrap_or_default();
+        let (missing, latest_recorded) = {
+            let history = self.cache.block_cache.read();
+            (history.missing(latest_number), history.contains(latest_number))
+        };
+
+        let mut records = vec![];
+        if !latest_recorded {
+            records.push(BlockRecord::from_block(&latest_block));
+        }
+        for block_number in missing {
+            if let Some(block) = self.get_block_by_number(block_number).await? {
+                records.push(BlockRecord::from_block(&block));
+            }
         }
 
+        // Fetching tracks first seen times, transactions which only just arrived aren't charged to these blocks
+        let pending = self.fetch_pending(unix_timestamp()).await?;
+
+        for record in &mut records {
+            record.skipped_transactions = pending
+                .iter()
+                .filter(|tx| record.could_have_fit(tx, self.cache.first_seen(&tx.tx_hash)))
+                .count() as u64;
+
+            tracing::info!(
+                block_number = record.number,
+                fee_recipient = ?record.fee_recipient,
+                extra_data = record.extra_data,
+                skipped_transactions = record.skipped_transactions,
+                "Recorded block"
+            );
+        }
+
+        let block_numbers = records.iter().map(|record| record.number).collect();
+        let (block_history, builder_skips) = {
+            let mut history = self.cache.block_cache.write();
+            records.into_iter().for_each(|record| history.insert(record));
+            (history.records(), history.builder_report())
+        };
+        self.status.update(|status| {
+            status.block_history = block_history;
+            status.builder_skips = builder_skips;
+        });
+
+        self.spawn_builder_attribution(block_numbers);
+
         Ok(())
     }
 
+    /// Attribute new blocks to the builders the relay delivered them for in the background, so
+    /// a slow relay data API doesn't hold up the inclusion lists
+    fn spawn_builder_attribution(&self, block_numbers: Vec<u64>) {
+        let http_client = self.http_client.clone();
+        let relay = self.il_config.relay.clone();
+        let cache = self.cache.clone();
+        let status = self.status.clone();
+
+        tokio::spawn(async move {
+            for block_number in block_numbers {
+                let builder_pubkey = match get_delivered_builder_pubkey(&http_client, &relay, block_number).await {
+                    Ok(Some(builder_pubkey)) => builder_pubkey,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        tracing::warn!(?err, block_number, "Failed to fetch the delivered payload");
+                        continue;
+                    }
+                };
+                tracing::info!(block_number, builder = builder_pubkey, "Attributed block to builder");
+
+                let (block_history, builder_skips) = {
+                    let mut history = cache.block_cache.write();
+                    history.set_builder(block_number, builder_pubkey);
+                    (history.records(), history.builder_report())
+                };
+                status.update(|status| {
+                    status.block_history = block_history;
+                    status.builder_skips = builder_skips;
+                });
+            }
+        });
+    }
+
+    /// Pending transactions merged from the mempool sources, recording their health
+    async fn fetch_pending(&self, now: u64) -> Result<Vec<Transaction>, InclusionListBoostError> {
+        let pending = self.mempool.pending(now).await;
+
+        let mempool_sources = self.mempool.health();
+        self.status.update(|status| status.mempool_sources = mempool_sources);
+
+        let pending = pending?;
+        self.track_pending(&pending, now);
+        Ok(pending)
+    }
+
+    /// Update first seen times with the mempool and queued inclusion requests
+    fn track_pending(&self, pending: &[Transaction], now: u64) {
+        let pending = pending
+            .iter()
+            .map(|tx| tx.tx_hash)
+            .chain(self.cache.inclusion_requests.transactions().iter().map(|tx| tx.tx_hash))
+            .collect::<HashSet<_>>();
+
+        self.cache.track_pending(&pending, now);
+    }
+
     async fn get_block_by_number(&self, block_number: u64) -> Result<Option<Block>, InclusionListBoostError> {
         self.eth_provider
-            .get_block_by_number(alloy::eips::BlockNumberOrTag::Number(block_number), true)
+            .get_block_by_number(BlockNumberOrTag::Number(block_number), true)
             .await
             .map_err(|e| {
                 e.into()
             })
     }
 
-    async fn get_block_number_by_slot(&self, slot: u64) -> Result<Option<u64>, InclusionListBoostError> {
+    /// Apply the policy, drop what can't pay the next block's fees and order the rest by
+    /// nonce, as seen by the local node. Only senders missing from `account_nonces` are looked up
+    async fn prepare_candidates(
+        &self,
+        latest_block: &Block,
+        pending_txs: Vec<Transaction>,
+        account_nonces: &mut AccountNonces,
+    ) -> (Vec<Transaction>, Vec<DroppedCandidate>) {
+        let (candidates, mut dropped) = self.filter_candidates(latest_block, pending_txs);
 
-        tracing::info!(slot, "Get block number by slot");
+        lookup_nonces(&self.eth_provider, candidates.iter().map(|tx| tx.from), account_nonces).await;
+        let (candidates, nonce_dropped) = order_with_nonces(account_nonces, candidates);
+        tracing::info!(
+            dropped_count = nonce_dropped.len(),
+            "Ordered candidates by sender nonce, dropped stale and gapped transactions"
+        );
+        dropped.extend(nonce_dropped);
 
-        let url = format!("{}/eth/v1/beacon/blocks/{}", self.il_config.beacon_api, slot);
-        let res = reqwest::get(url).await?;
-        let json: serde_json::Value = serde_json::from_str(&res.text().await?)?;
-        
-        let Some(block_number) = json.pointer("/data/message/body/execution_payload/block_number") else {
-            return Ok(None);
-        };
+        let (candidates, limit_dropped) = self.policy.limit_per_sender(candidates);
+        dropped.extend(limit_dropped);
+
+        (candidates, dropped)
+    }
+
+    /// Blob parameters of the block after `parent`, a slot of the chain's slot duration later
+    fn next_blob_params(&self, parent: &Header) -> BlobParams {
+        let seconds_per_slot = self.slot_clock.map_or(SECONDS_PER_SLOT, |clock| clock.seconds_per_slot());
+        self.il_config.blob_schedule.after(parent, seconds_per_slot)
+    }
+
+    /// Apply the policy and drop what can't pay the next block's fees
+    fn filter_candidates(
+        &self,
+        latest_block: &Block,
+        pending_txs: Vec<Transaction>,
+    ) -> (Vec<Transaction>, Vec<DroppedCandidate>) {
+        let (candidates, mut dropped) = self.policy.apply(pending_txs);
+
+        let fees = NextBlockFees::from_parent(
+            &latest_block.header,
+            self.il_config.base_fee_headroom_slots,
+            self.next_blob_params(&latest_block.header),
+        );
+        let (candidates, fee_dropped) = fees.filter(candidates);
+        tracing::info!(
+            base_fee = fees.base_fee,
+            blob_base_fee = ?fees.blob_base_fee,
+            dropped_count = fee_dropped.len(),
+            "Dropped candidates that can't pay the next block's fees"
+        );
+        dropped.extend(fee_dropped);
+
+        (candidates, dropped)
+    }
 
-        let Some(block_number_str) = block_number.as_str() else {
-            return Ok(None);
+    /// Build a list from the local view and from every vantage point and keep the transactions
+    /// listed by enough of them. `pending_txs` is the local view, the inclusion requests in it
+    /// are considered by every vantage point as well. A vantage point that can't be reached
+    /// doesn't vote. Every view is filtered, ordered by nonce against the local node and tagged
+    /// for sanctions the way the local list is, with one nonce lookup and one tagging pass shared
+    /// by all views. Returns the merged transactions, their sources and the sanctions tags
+    async fn aggregate_vantage_points(
+        &self,
+        latest_block: &Block,
+        pending_txs: Vec<Transaction>,
+        requested: &HashSet<B256>,
+        overrides: &SlotOverrides,
+        account_nonces: &mut AccountNonces,
+        now: u64,
+    ) -> (Vec<Transaction>, HashMap<B256, Vec<String>>, HashMap<B256, Vec<SanctionsTag>>) {
+        let requested = pending_txs.iter().filter(|tx| requested.contains(&tx.tx_hash)).cloned().collect::<Vec<_>>();
+        let mut views = vec![(LOCAL_VANTAGE_POINT, pending_txs)];
+
+        let tx_pools = join_all(self.vantage_points.iter().map(|(_, provider)| async move {
+            match tokio::time::timeout(POLL_TIMEOUT, provider.txpool_content()).await {
+                Ok(result) => result.map_err(InclusionListBoostError::from),
+                Err(_) => Err("timed out".to_string().into()),
+            }
+        }))
+        .await;
+
+        for ((name, _), tx_pool) in self.vantage_points.iter().zip(tx_pools) {
+            let tx_pool = match tx_pool {
+                Ok(tx_pool) => tx_pool,
+                Err(err) => {
+                    tracing::warn!(?err, vantage_point = name, "Failed to fetch the vantage point's memory pool");
+                    continue;
+                }
+            };
+
+            let mut view = requested.clone();
+            for (_, transactions) in tx_pool.pending {
+                view.extend(transactions.into_values().map(|tx| self.mempool.from_node(tx)).filter(|tx| {
+                    !requested.iter().any(|requested| requested.tx_hash == tx.tx_hash) &&
+                        !overrides.removed.contains(&tx.tx_hash) &&
+                        !overrides.pinned.iter().any(|pinned| pinned.tx_hash == tx.tx_hash)
+                }));
+            }
+            views.push((name.as_str(), view));
+        }
+
+        let filtered = views
+            .into_iter()
+            .map(|(name, view)| {
+                let (candidates, _) = self.filter_candidates(latest_block, view.clone());
+                (name, view, candidates)
+            })
+            .collect::<Vec<_>>();
+
+        let mut union = HashMap::new();
+        for (_, _, candidates) in &filtered {
+            union.extend(candidates.iter().map(|tx| (tx.tx_hash, tx.clone())));
+        }
+        let union = union.into_values().collect::<Vec<_>>();
+        lookup_nonces(&self.eth_provider, union.iter().map(|tx| tx.from), account_nonces).await;
+        let sanctions_tags = self.sanctions_tags(&union).await;
+
+        let mut committee = Committee::default();
+        let mut seen = HashMap::new();
+        for (name, view, candidates) in filtered {
+            let (candidates, _) = order_with_nonces(account_nonces, candidates);
+            let (candidates, _) = self.policy.limit_per_sender(candidates);
+            let selected = InclusionBoost::select_candidates(
+                &candidates,
+                &latest_block.header,
+                self.il_config.gas_budget,
+                self.next_blob_params(&latest_block.header),
+                &self.cache.block_cache.read(),
+                &sanctions_tags,
+                |tx| self.cache.first_seen(&tx.tx_hash),
+                now,
+            );
+            tracing::info!(
+                vantage_point = name,
+                selected_count = selected.len(),
+                "Built inclusion list from vantage point"
+            );
+
+            committee.vote(name, selected.iter().map(|candidate| candidate.tx.tx_hash));
+            seen.extend(view.into_iter().map(|tx| (tx.tx_hash, tx)));
+        }
+
+        let sources = committee.aggregate(self.il_config.aggregation);
+        tracing::info!(
+            aggregation = ?self.il_config.aggregation,
+            listed_count = sources.len(),
+            "Aggregated vantage point lists"
+        );
+
+        let pending_txs = seen.into_values().filter(|tx| sources.contains_key(&tx.tx_hash)).collect();
+        (pending_txs, sources, sanctions_tags)
+    }
+
+    /// Sanctions tags of `candidates`, empty without a sanctions list
+    async fn sanctions_tags(&self, candidates: &[Transaction]) -> HashMap<B256, Vec<SanctionsTag>> {
+        let Some(sanctions) = &self.sanctions else {
+            return HashMap::new();
         };
-        Ok(Some(block_number_str.parse::<u64>()?))
+
+        let sanctions_tags = sanctions.tag(&self.eth_provider, candidates, self.il_config.trace_sanctioned_calls).await;
+        for (tx_hash, tags) in &sanctions_tags {
+            tracing::info!(?tx_hash, ?tags, "Candidate touches sanctioned addresses");
+        }
+        sanctions_tags
     }
 
-    /// Builds an inclusion list for slot N by comparing pending transactions in the mem pool
-    /// with the block from slot N - 1
+    /// Builds an inclusion list for the target slot by comparing pending transactions in the
+    /// mem pool with the latest block
     async fn build_inclusion_list(
         &self,
         latest_block: &Block,
-        slot: u64,
-        validator_index: usize,
+        target: ListTarget,
     ) -> Result<Option<InclusionList>, InclusionListBoostError> {
-        let mut pending_txs = vec![];
-        let tx_pool = self.eth_provider.txpool_content().await?;
+        let ListTarget {
+            slot,
+            validator_index,
+            il_type,
+            revision,
+        } = target;
+        let conditional = self.il_config.conditional_validators.contains(&validator_index);
+        let unsigned_list =
+        InclusionList::new(slot, validator_index, vec![])
+            .with_type(il_type)
+            .with_conditional(conditional)
+            .with_revision(revision);
+
+        let included = latest_block.transactions.txns().map(|tx| tx.hash).collect::<HashSet<_>>();
+        self.cache.inclusion_requests.prune(&included);
+
+        // User submitted inclusion requests are considered first, ahead of the mempool
+        let mut pending_txs = self.cache.inclusion_requests.transactions();
+        let requested_count = pending_txs.len();
+        let requested = pending_txs.iter().map(|tx| tx.tx_hash).collect::<HashSet<_>>();
+        let now = unix_timestamp();
+        let mempool_txs = self.fetch_pending(now).await?;
+        pending_txs.extend(mempool_txs.into_iter().filter(|tx| !requested.contains(&tx.tx_hash)));
+
+        let overrides = self.cache.overrides.read().get(&target.key()).cloned().unwrap_or_default();
+        if overrides.frozen {
+            if let Some(cached) = self.cache.inclusion_list_cache.read().get(&target.key()) {
+                tracing::info!(slot, "Inclusion list is frozen, submitting the cached list");
+                let constraints = cached.transactions.iter().map(|tx| tx.constraint.clone()).collect();
+                return Ok(Some(unsigned_list.with_constraints(constraints)));
+            }
+        }
+
+        pending_txs.retain(|tx| {
+            !overrides.removed.contains(&tx.tx_hash) &&
+                !overrides.pinned.iter().any(|pinned| pinned.tx_hash == tx.tx_hash)
+        });
+
+        let mut account_nonces = AccountNonces::new();
+        let (candidates, mut dropped, mut sources, sanctions_tags) = if self.vantage_points.is_empty() {
+            let (candidates, dropped) = self.prepare_candidates(latest_block, pending_txs, &mut account_nonces).await;
+            let sanctions_tags = self.sanctions_tags(&candidates).await;
+            (candidates, dropped, HashMap::new(), sanctions_tags)
+        } else {
+            let (pending_txs, sources, mut sanctions_tags) = self
+                .aggregate_vantage_points(latest_block, pending_txs, &requested, &overrides, &mut account_nonces, now)
+                .await;
+            // The merged candidates were all tagged as part of some view
+            let (candidates, dropped) = self.prepare_candidates(latest_block, pending_txs, &mut account_nonces).await;
+            sanctions_tags.retain(|tx_hash, _| candidates.iter().any(|tx| tx.tx_hash == *tx_hash));
+            (candidates, dropped, sources, sanctions_tags)
+        };
 
+        let selected = InclusionBoost::select_candidates(
+            &candidates,
+            &latest_block.header,
+            self.il_config.gas_budget,
+            self.next_blob_params(&latest_block.header),
+            &self.cache.block_cache.read(),
+            &sanctions_tags,
+            |tx| self.cache.first_seen(&tx.tx_hash),
+            now,
+        );
         tracing::info!(
-            transaction_count = tx_pool.pending.len(),
-            "Fetched pending transactions from the local memory pool"
+            candidate_count = candidates.len(),
+            selected_count = selected.len(),
+            gas_budget = ?self.il_config.gas_budget,
+            "Selected candidates within the inclusion list gas budget"
         );
+        let mut evidence =
+            selected.iter().map(|candidate| (candidate.tx.tx_hash, candidate.evidence)).collect::<HashMap<_, _>>();
+        let mut filtered_transactions = selected.into_iter().map(|candidate| candidate.tx).collect::<Vec<_>>();
 
-        for (_, transactions) in tx_pool.pending {
-            let transactions = transactions
-                .iter()
-                .map(|(_, tx)| tx.clone().into())
-                .collect::<Vec<Transaction>>();
+        let mut simulated_gas_used = None;
+        if self.il_config.simulate_candidates {
+            let outcome = Simulator::new(&self.eth_provider).simulate(filtered_transactions).await;
 
-            pending_txs.extend(transactions);
-        }
+            tracing::info!(
+                executable_count = outcome.executable.len(),
+                failed_count = outcome.dropped.len(),
+                unsimulated_count = outcome.unsimulated,
+                gas_used = outcome.gas_used,
+                "Simulated inclusion list candidates against the head state"
+            );
 
-        let filtered_transactions =
-            InclusionBoost::get_filtered_transactions(&pending_txs, latest_block);
+            filtered_transactions = outcome.executable;
+            dropped.extend(outcome.dropped);
+            simulated_gas_used = Some(outcome.gas_used);
+        }
 
         tracing::info!(
             transaction_count = filtered_transactions.len(),
+            ?il_type,
+            conditional,
+            pinned_count = overrides.pinned.len(),
+            requested_count,
+            dropped_count = dropped.len(),
             "Identified a list of potentially filtered transactions"
         );
 
@@ -204,14 +1209,143 @@ impl InclusionSideCar {
         //     return Ok(None);
         // };
 
-        Ok(Some(InclusionList::new(
-            slot,
-            validator_index,
-            filtered_transactions,
-        )))
+        evidence.retain(|tx_hash, _| filtered_transactions.iter().any(|tx| tx.tx_hash == *tx_hash));
+        sources.retain(|tx_hash, _| evidence.contains_key(tx_hash));
+
+        let mut transactions = overrides.pinned;
+        transactions.extend(filtered_transactions.into_iter().map(ListedTransaction::from));
+
+        let mut cached = CachedInclusionList::new(unsigned_list, transactions, overrides.frozen);
+        cached.dropped = dropped;
+        cached.simulated_gas_used = simulated_gas_used;
+        cached.evidence = evidence;
+        cached.sanctions_tags = sanctions_tags;
+        cached.sources = sources;
+        let inclusion_list = cached.inclusion_list.clone();
+        self.cache.inclusion_list_cache.write().insert(target.key(), cached);
+
+        Ok(Some(inclusion_list))
     }
 }
 
+/// The lists to publish after the head moved to `head_slot`. A spot list needs one of our
+/// keys to propose the next slot, a forward list additionally needs the proposer of the
+/// slot after it to run this module or to have opted in
+pub fn select_list_targets(
+    mode: InclusionListMode,
+    head_slot: u64,
+    proposer_of: impl Fn(u64) -> Option<usize>,
+    is_managed: impl Fn(usize) -> bool,
+    forward_opted_in: &HashSet<usize>,
+) -> Vec<ListTarget> {
+    // Get the next slots proposer
+    let Some(next_proposer) = proposer_of(head_slot + 1) else {
+        tracing::info!("At end of epoch, waiting");
+        return vec![];
+    };
+
+    if !is_managed(next_proposer) {
+        return vec![];
+    }
+
+    let mut targets = vec![];
+    if mode.publishes(InclusionListType::Spot) {
+        targets.push(ListTarget {
+            slot: head_slot + 1,
+            validator_index: next_proposer,
+            il_type: InclusionListType::Spot,
+            revision: 0,
+        });
+    }
+
+    if mode.publishes(InclusionListType::Forward) {
+        let forward_slot = head_slot + 2;
+        match proposer_of(forward_slot) {
+            Some(validator_index) if is_managed(validator_index) || forward_opted_in.contains(&validator_index) => {
+                targets.push(ListTarget {
+                    slot: forward_slot,
+                    validator_index: next_proposer,
+                    il_type: InclusionListType::Forward,
+                    revision: 0,
+                });
+            }
+            forward_proposer => tracing::info!(
+                slot = forward_slot,
+                validator_index = forward_proposer,
+                "Forward slot proposer didn't opt in, skipping forward inclusion list"
+            ),
+        }
+    }
+
+    targets
+}
+
+/// Builder pubkey of the payload the relay delivered for `block_number`, if it delivered one
+async fn get_delivered_builder_pubkey(
+    http_client: &reqwest::Client,
+    relay: &str,
+    block_number: u64,
+) -> Result<Option<String>, InclusionListBoostError> {
+    // The relay url carries the relay pubkey as user info, which the data API doesn't expect
+    let mut url = reqwest::Url::parse(relay).map_err(|err| err.to_string())?;
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url.set_path("/relay/v1/data/bidtraces/proposer_payload_delivered");
+    url.set_query(Some(&format!("block_number={block_number}")));
+
+    let res = http_client.get(url).send().await?;
+    let json: serde_json::Value = serde_json::from_str(&res.text().await?)?;
+
+    Ok(json.pointer("/0/builder_pubkey").and_then(|pubkey| pubkey.as_str()).map(str::to_string))
+}
+
+/// Revisions still due for the lists last published. The main loop publishes each one once it
+/// is due, so head events keep being processed in between
+pub struct RevisionSchedule {
+    latest_block: Block,
+    targets: Vec<ListTarget>,
+    /// Revision published next
+    pub revision: u64,
+    /// Unix time in milliseconds the revision is due
+    pub due: u64,
+    interval: u64,
+    cutoff: u64,
+}
+
+impl RevisionSchedule {
+    /// Revisions every `interval` after `now` and before `cutoff`, none when the first one
+    /// would already be too late or `interval` is 0
+    pub fn new(latest_block: Block, targets: Vec<ListTarget>, now: u64, cutoff: u64, interval: u64) -> Option<Self> {
+        let due = now + interval;
+        (interval > 0 && due < cutoff).then_some(Self {
+            latest_block,
+            targets,
+            revision: 1,
+            due,
+            interval,
+            cutoff,
+        })
+    }
+
+    /// The revision after this one, none past the cutoff. Times that a slow revision ran over
+    /// are skipped rather than published late
+    pub fn advance(mut self, now: u64) -> Option<Self> {
+        self.revision += 1;
+        self.due += self.interval;
+        while self.due <= now {
+            self.due += self.interval;
+        }
+        (self.due < self.cutoff).then_some(self)
+    }
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or_default()
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_millis() as u64).unwrap_or_default()
+}
 
 async fn get_validator_index(beacon_url: &str, validator_pubkey: &str) -> Result<Option<u64>, LookaheadError>{
     let url = format!("{beacon_url}/eth/v1/beacon/states/head/validators?id={validator_pubkey}");
@@ -227,4 +1361,70 @@ async fn get_validator_index(beacon_url: &str, validator_pubkey: &str) -> Result
     };
 
     Ok(Some(validator_index_str.parse::<u64>()?))
-}
\ No newline at end of file
+}
+
+/// Execution block number of the head block, found by its block root `head_root`. When the root
+/// can't be resolved, walks back from `head_slot` past missed slots to the latest block
+pub async fn resolve_head_block_number(
+    client: &reqwest::Client,
+    beacon_url: &str,
+    head_slot: u64,
+    head_root: B256,
+) -> Result<Option<u64>, InclusionListBoostError> {
+    match get_block_number_by_id(client, beacon_url, &head_root.to_string()).await {
+        Ok(Some(block_number)) => return Ok(Some(block_number)),
+        Ok(None) => tracing::warn!(root = %head_root, "Head block has no execution payload"),
+        Err(err) => tracing::warn!(?err, root = %head_root, "Failed to fetch the head block by root"),
+    }
+
+    // A slot that fails to resolve is skipped like a missed one, the error is only returned
+    // when no older block was found either
+    let mut last_error = None;
+    for slot in (head_slot.saturating_sub(MAX_MISSED_SLOTS)..=head_slot).rev() {
+        match get_block_number_by_slot(client, beacon_url, slot).await {
+            Ok(Some(block_number)) => {
+                if slot < head_slot {
+                    tracing::info!(slot, missed_slots = head_slot - slot, "Walked back past missed slots");
+                }
+                return Ok(Some(block_number));
+            }
+            Ok(None) => {}
+            Err(err) => {
+                tracing::warn!(?err, slot, "Failed to fetch the block of slot");
+                last_error = Some(err);
+            }
+        }
+    }
+
+    last_error.map_or(Ok(None), Err)
+}
+
+/// Execution block number of the block in `slot`, `None` when the slot was missed
+async fn get_block_number_by_slot(
+    client: &reqwest::Client,
+    beacon_url: &str,
+    slot: u64,
+) -> Result<Option<u64>, InclusionListBoostError> {
+    tracing::info!(slot, "Get block number by slot");
+    get_block_number_by_id(client, beacon_url, &slot.to_string()).await
+}
+
+/// Execution block number of the beacon block `block_id`, a slot or a block root
+async fn get_block_number_by_id(
+    client: &reqwest::Client,
+    beacon_url: &str,
+    block_id: &str,
+) -> Result<Option<u64>, InclusionListBoostError> {
+    let url = format!("{beacon_url}/eth/v2/beacon/blocks/{block_id}");
+    let res = client.get(url).send().await?;
+    let json: serde_json::Value = serde_json::from_str(&res.text().await?)?;
+
+    let Some(block_number) = json.pointer("/data/message/body/execution_payload/block_number") else {
+        return Ok(None);
+    };
+
+    let Some(block_number_str) = block_number.as_str() else {
+        return Ok(None);
+    };
+    Ok(Some(block_number_str.parse::<u64>()?))
+}
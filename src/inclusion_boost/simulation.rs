@@ -0,0 +1,264 @@
+use alloy::{
+    primitives::Address,
+    providers::{Provider, RootProvider},
+    transports::{http::Http, RpcError, TransportErrorKind},
+};
+use serde_json::{json, Value};
+
+use super::types::{DropReason, DroppedCandidate, Transaction};
+
+/// JSON-RPC error code returned by nodes that don't implement a method
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// JSON-RPC errors of the node itself rather than of the simulated calls: parse error, invalid
+/// request, internal error and limit exceeded
+const NODE_ERROR_CODES: [i64; 4] = [-32700, -32600, -32603, -32005];
+
+/// Candidates that executed successfully against the head state
+#[derive(Debug, Default)]
+pub struct SimulationOutcome<'a> {
+    pub executable: Vec<&'a Transaction>,
+    pub dropped: Vec<DroppedCandidate>,
+    pub gas_used: u128,
+    /// Kept without simulating because the node couldn't simulate them, counted at their gas limit
+    pub unsimulated: usize,
+}
+
+/// Why a simulation didn't produce a result for every call
+#[derive(Debug)]
+enum SimulationError {
+    /// The node doesn't implement the simulation method
+    MethodNotFound,
+    /// The node executed the calls and rejected them
+    Execution(String),
+    /// The node couldn't be reached or answered with something other than a simulation result
+    Node(String),
+}
+
+impl From<RpcError<TransportErrorKind>> for SimulationError {
+    fn from(err: RpcError<TransportErrorKind>) -> Self {
+        match err.as_error_resp() {
+            Some(payload) if payload.code == METHOD_NOT_FOUND => SimulationError::MethodNotFound,
+            Some(payload) if !NODE_ERROR_CODES.contains(&payload.code) => SimulationError::Execution(err.to_string()),
+            _ => SimulationError::Node(err.to_string()),
+        }
+    }
+}
+
+/// Simulates inclusion list candidates on top of the latest block so that transactions
+/// which would fail (stale nonce, insufficient balance, fee caps, reverts) are not listed
+pub struct Simulator<'p> {
+    eth_provider: &'p RootProvider<Http<reqwest::Client>>,
+}
+
+impl<'p> Simulator<'p> {
+    pub fn new(eth_provider: &'p RootProvider<Http<reqwest::Client>>) -> Self {
+        Self { eth_provider }
+    }
+
+    /// Simulate each sender's candidates in nonce order with `eth_simulateV1`,
+    /// falling back to `eth_estimateGas` per transaction if the node doesn't support it.
+    /// Only execution failures drop candidates, the ones a node error left unsimulated are kept
+    pub async fn simulate<'a>(&self, candidates: Vec<&'a Transaction>) -> SimulationOutcome<'a> {
+        let mut outcome = SimulationOutcome::default();
+        let mut groups = group_by_sender(candidates);
+        groups.iter_mut().for_each(|sender_txs| sender_txs.sort_by_key(|tx| tx.nonce));
+
+        for (index, sender_txs) in groups.iter().enumerate() {
+            match self.simulate_sequence(sender_txs).await {
+                Ok(results) => outcome.record(sender_txs, results),
+                Err(SimulationError::MethodNotFound) => {
+                    tracing::warn!("eth_simulateV1 is not supported, falling back to eth_estimateGas");
+                    let remaining = groups[index..].iter().flatten().copied().collect();
+                    self.estimate_each(&mut outcome, remaining).await;
+                    break;
+                }
+                // A validation failure rejects the whole sequence, find the first failing transaction
+                Err(SimulationError::Execution(_)) => self.simulate_prefixes(&mut outcome, sender_txs).await,
+                Err(SimulationError::Node(error)) => {
+                    tracing::warn!(error, "Failed to simulate candidates, keeping them unsimulated");
+                    outcome.keep_unsimulated(sender_txs);
+                }
+            }
+        }
+
+        outcome
+    }
+
+    /// Simulate growing prefixes of `sender_txs` until one fails. That transaction and
+    /// every later one, which depends on its nonce, are dropped
+    async fn simulate_prefixes<'a>(
+        &self,
+        outcome: &mut SimulationOutcome<'a>,
+        sender_txs: &[&'a Transaction],
+    ) {
+        let mut last_results = vec![];
+
+        for end in 1..=sender_txs.len() {
+            match self.simulate_sequence(&sender_txs[..end]).await {
+                Ok(results) => last_results = results,
+                Err(SimulationError::Execution(error)) => {
+                    outcome.record(&sender_txs[..end - 1], last_results);
+
+                    outcome.dropped.extend(sender_txs[end - 1..].iter().map(|tx| DroppedCandidate {
+                        tx_hash: tx.tx_hash,
+                        reason: DropReason::SimulationFailed { error: error.clone() },
+                    }));
+                    return;
+                }
+                // The node failed rather than the transaction, the rest isn't known to fail
+                Err(err) => {
+                    tracing::warn!(?err, "Failed to simulate candidates, keeping them unsimulated");
+                    outcome.record(&sender_txs[..end - 1], last_results);
+                    outcome.keep_unsimulated(&sender_txs[end - 1..]);
+                    return;
+                }
+            }
+        }
+
+        outcome.record(sender_txs, last_results);
+    }
+
+    /// Fallback for nodes without `eth_simulateV1`, every remaining candidate is checked on its own
+    async fn estimate_each<'a>(&self, outcome: &mut SimulationOutcome<'a>, candidates: Vec<&'a Transaction>) {
+        for tx in candidates {
+            match self
+                .eth_provider
+                .raw_request::<_, Value>("eth_estimateGas".into(), (call_object(tx), "latest"))
+                .await
+            {
+                Ok(gas) => {
+                    outcome.gas_used += parse_quantity(&gas).unwrap_or(tx.gas);
+                    outcome.executable.push(tx);
+                }
+                Err(err) => match SimulationError::from(err) {
+                    SimulationError::Execution(error) => outcome.dropped.push(DroppedCandidate {
+                        tx_hash: tx.tx_hash,
+                        reason: DropReason::SimulationFailed { error },
+                    }),
+                    err => {
+                        tracing::warn!(?err, tx_hash = ?tx.tx_hash, "Failed to estimate gas, keeping the candidate");
+                        outcome.keep_unsimulated(&[tx]);
+                    }
+                },
+            }
+        }
+    }
+
+    /// Run `txs` back to back in a single simulated block with full validation. A result
+    /// without one call per transaction is a node error
+    async fn simulate_sequence(&self, txs: &[&Transaction]) -> Result<Vec<CallResult>, SimulationError> {
+        if txs.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let payload = json!({
+            "blockStateCalls": [{ "calls": txs.iter().map(|tx| call_object(tx)).collect::<Vec<_>>() }],
+            "validation": true,
+        });
+
+        let blocks = self
+            .eth_provider
+            .raw_request::<_, Value>("eth_simulateV1".into(), (payload, "latest"))
+            .await?;
+
+        let calls = blocks.pointer("/0/calls").and_then(Value::as_array).cloned().unwrap_or_default();
+        if calls.len() != txs.len() {
+            return Err(SimulationError::Node(format!(
+                "expected {} call results, got {}",
+                txs.len(),
+                calls.len()
+            )));
+        }
+
+        Ok(calls
+            .iter()
+            .map(|call| CallResult {
+                success: call.get("status").and_then(Value::as_str) == Some("0x1"),
+                gas_used: call.get("gasUsed").and_then(parse_quantity).unwrap_or_default(),
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug)]
+struct CallResult {
+    success: bool,
+    gas_used: u128,
+}
+
+impl<'a> SimulationOutcome<'a> {
+    fn keep_unsimulated(&mut self, txs: &[&'a Transaction]) {
+        self.gas_used += txs.iter().map(|tx| tx.gas).sum::<u128>();
+        self.unsimulated += txs.len();
+        self.executable.extend_from_slice(txs);
+    }
+
+    fn record(&mut self, txs: &[&'a Transaction], results: Vec<CallResult>) {
+        for (tx, result) in txs.iter().zip(results) {
+            if result.success {
+                self.gas_used += result.gas_used;
+                self.executable.push(*tx);
+            } else {
+                self.dropped.push(DroppedCandidate {
+                    tx_hash: tx.tx_hash,
+                    reason: DropReason::Reverted,
+                });
+            }
+        }
+    }
+}
+
+/// Group transactions by sender, keeping the order in which senders first appear
+fn group_by_sender(candidates: Vec<&Transaction>) -> Vec<Vec<&Transaction>> {
+    let mut senders: Vec<Address> = vec![];
+    let mut groups: Vec<Vec<&Transaction>> = vec![];
+
+    for tx in candidates {
+        match senders.iter().position(|sender| sender == &tx.from) {
+            Some(index) => groups[index].push(tx),
+            None => {
+                senders.push(tx.from);
+                groups.push(vec![tx]);
+            }
+        }
+    }
+
+    groups
+}
+
+/// `eth_call` style call object for `tx`
+pub fn call_object(tx: &Transaction) -> Value {
+    let mut call = json!({
+        "from": tx.from,
+        "gas": format!("{:#x}", tx.gas),
+        "value": format!("{:#x}", tx.value),
+        "input": tx.bytes,
+        "nonce": format!("{:#x}", tx.nonce),
+    });
+
+    if let Some(to) = tx.to {
+        call["to"] = json!(to);
+    }
+
+    if let Some(max_fee_per_blob_gas) = tx.max_fee_per_blob_gas {
+        call["maxFeePerBlobGas"] = json!(format!("{max_fee_per_blob_gas:#x}"));
+        call["blobVersionedHashes"] = json!(tx.blob_versioned_hashes);
+    }
+
+    match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+        (Some(max_fee), Some(max_priority_fee)) => {
+            call["maxFeePerGas"] = json!(format!("{max_fee:#x}"));
+            call["maxPriorityFeePerGas"] = json!(format!("{max_priority_fee:#x}"));
+        }
+        (Some(gas_price), None) => call["gasPrice"] = json!(format!("{gas_price:#x}")),
+        _ => {}
+    }
+
+    call
+}
+
+fn parse_quantity(value: &Value) -> Option<u128> {
+    let quantity = value.as_str()?.strip_prefix("0x")?;
+    u128::from_str_radix(quantity, 16).ok()
+}
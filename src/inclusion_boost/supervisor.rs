@@ -0,0 +1,110 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use parking_lot::RwLock;
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use super::{error::InclusionListBoostError, sidecar::InclusionSideCar};
+
+/// How long to wait before the sidecar first starts, so the signer and beacon node are up
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+
+/// How long to wait before restarting the sidecar after it exits or panics
+const RESTART_BACKOFF: Duration = Duration::from_secs(5);
+
+/// A failure while processing a single slot
+#[derive(Debug, Clone, Serialize)]
+pub struct SlotFailure {
+    pub slot: u64,
+    pub error: String,
+}
+
+/// Liveness and failure counters for the inclusion list sidecar,
+/// shared between the sidecar task and its supervisor
+#[derive(Debug, Default)]
+pub struct SidecarHealth {
+    alive: AtomicBool,
+    restarts: AtomicU64,
+    slot_failures: AtomicU64,
+    last_failure: RwLock<Option<SlotFailure>>,
+}
+
+impl SidecarHealth {
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::Relaxed)
+    }
+
+    pub fn restarts(&self) -> u64 {
+        self.restarts.load(Ordering::Relaxed)
+    }
+
+    pub fn slot_failures(&self) -> u64 {
+        self.slot_failures.load(Ordering::Relaxed)
+    }
+
+    pub fn last_failure(&self) -> Option<SlotFailure> {
+        self.last_failure.read().clone()
+    }
+
+    fn set_alive(&self, alive: bool) {
+        self.alive.store(alive, Ordering::Relaxed);
+    }
+
+    /// Record that the work for `slot` failed. The sidecar keeps running
+    pub fn record_slot_failure(&self, slot: u64, error: &InclusionListBoostError) {
+        let failures = self.slot_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::error!(slot, failures, ?error, "Failed to process slot, continuing with the next one");
+
+        *self.last_failure.write() = Some(SlotFailure {
+            slot,
+            error: format!("{error:?}"),
+        });
+    }
+}
+
+/// Run the sidecar and restart it whenever it exits, returns an error or panics
+pub async fn supervise(sidecar: Arc<Mutex<InclusionSideCar>>, health: Arc<SidecarHealth>) {
+    restart_loop(health, STARTUP_DELAY, RESTART_BACKOFF, move || {
+        let task_sidecar = sidecar.clone();
+        async move { task_sidecar.lock().await.run().await }
+    })
+    .await
+}
+
+/// Run the task `start` returns once `startup_delay` passed, and start a new one `backoff` after
+/// it exits, returns an error or panics. Restarts don't wait for the startup delay again
+pub async fn restart_loop<F, Fut>(health: Arc<SidecarHealth>, startup_delay: Duration, backoff: Duration, mut start: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), InclusionListBoostError>> + Send + 'static,
+{
+    tokio::time::sleep(startup_delay).await;
+
+    loop {
+        let handle = tokio::spawn(start());
+        health.set_alive(true);
+
+        match handle.await {
+            Ok(Ok(())) => tracing::warn!("Inclusion list sidecar exited"),
+            Ok(Err(err)) => tracing::error!(?err, "Inclusion list sidecar failed"),
+            Err(err) if err.is_panic() => tracing::error!("Inclusion list sidecar panicked"),
+            Err(err) => {
+                tracing::error!(?err, "Inclusion list sidecar was cancelled, not restarting");
+                health.set_alive(false);
+                return;
+            }
+        }
+
+        health.set_alive(false);
+        let restarts = health.restarts.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::info!(restarts, "Restarting inclusion list sidecar in {:?}", backoff);
+        tokio::time::sleep(backoff).await;
+    }
+}
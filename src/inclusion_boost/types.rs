@@ -1,23 +1,37 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+use alloy::consensus::TxEnvelope;
+use alloy::eips::eip2718::{Decodable2718, Encodable2718};
+
 use alloy::hex::ToHexExt;
-use alloy::primitives::{keccak256, Bytes};
+use alloy::primitives::{keccak256, Address, Bytes, U256};
 use alloy::rpc::types::beacon::{BlsPublicKey, BlsSignature};
 use alloy::{network::TransactionResponse, primitives::B256};
+use cb_common::commit::request::SignedProxyDelegation;
 use ethereum_consensus::ssz::prelude::List;
 use parking_lot::RwLock;
 use reth_transaction_pool::PoolTransaction;
 use reth_transaction_pool::{test_utils::MockTransaction, ValidPoolTransaction};
 use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize, Serializer};
-use ssz_types::typenum::{U1, U32};
+use ssz_types::typenum::{U1, U32, U4096, U8192};
 use ssz_types::{FixedVector, VariableList};
+use tree_hash::{Hash256, PackedEncoding, TreeHash, TreeHashType};
 use tree_hash_derive::TreeHash;
 
+use super::{
+    bytes_to_array, delegation::DelegationManager, error::InclusionListBoostError, fees::DATA_GAS_PER_BLOB, history::BlockHistory,
+    inclusion_requests::InclusionRequestQueue, protection::ProtectionDb, sanctions::SanctionsTag, satisfaction::Satisfaction,
+    selection::CensorshipEvidence,
+};
+
 /// The BLS Domain Separator used in Ethereum 2.0.
 
-type MaxInclusionListLength = U1;
+/// Most constraints a list can carry. Far above what fits in a block's gas limit at 21000 gas
+/// per transaction, lists that would exceed it are cut off before they are cached
+pub const MAX_CONSTRAINTS_PER_LIST: usize = 8192;
+type MaxInclusionListLength = U8192;
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct InclusionListDelegateSignedMessage {
@@ -33,17 +47,135 @@ pub struct InclusionListDelegateMessage {
   pub gas_limit: u64
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InclusionListRevokeSignedMessage {
+  pub message: InclusionListRevokeMessage,
+  pub signature: BlsSignature
+}
+
+/// Withdraws the delegation of a slot's inclusion list authority to `preconfer_pubkey`
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, TreeHash)]
+pub struct InclusionListRevokeMessage {
+  pub preconfer_pubkey: BlsPublicKey,
+  pub slot_number: u64,
+  pub chain_id: u64,
+}
+
+
+/// Spot lists constrain the block of the signing proposer's own slot,
+/// forward lists the block of the slot after it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InclusionListType {
+    #[default]
+    Spot = 0,
+    Forward = 1,
+}
+
+impl From<u8> for InclusionListType {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => InclusionListType::Forward,
+            _ => InclusionListType::Spot,
+        }
+    }
+}
+
+/// Which inclusion lists the module publishes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InclusionListMode {
+    #[default]
+    Spot,
+    Forward,
+    Both,
+}
+
+impl InclusionListMode {
+    pub fn publishes(&self, il_type: InclusionListType) -> bool {
+        match self {
+            InclusionListMode::Spot => il_type == InclusionListType::Spot,
+            InclusionListMode::Forward => il_type == InclusionListType::Forward,
+            InclusionListMode::Both => true,
+        }
+    }
+}
+
+/// Wire format of published lists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InclusionListFormat {
+    /// `InclusionList` of hashed constraints, posted to the relay's constraints API
+    #[default]
+    Constraints,
+    /// EIP-7805 `InclusionList` carrying whole transactions
+    Focil,
+}
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, TreeHash)]
 pub struct InclusionList {
+    /// Slot whose block the list constrains
     pub slot: u64,
+    /// Validator signing the list, the proposer of `slot` for spot lists and of `slot - 1` for forward lists
     pub validator_index: usize,
-    pub constraints: FixedVector<FixedVector<Constraint, MaxInclusionListLength>, MaxInclusionListLength>,
+    /// `InclusionListType` as a byte, so the signature commits to it
+    pub inclusion_list_type: u8,
+    /// A conditional list is also satisfied by a block without room for any omitted constraint
+    pub conditional: bool,
+    /// Increases with every list the signer publishes for the slot, relays keep the highest
+    pub revision: u64,
+    pub constraints: FixedVector<VariableList<Constraint, MaxInclusionListLength>, U1>,
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Deserialize, TreeHash)]
+/// Blob sidecars are not available over the standard execution API, so blob transactions are
+/// marked with their versioned hashes and relays fetch the blobs themselves. The hashes are part
+/// of the signing root, `hash_tree_root({tx, blob_versioned_hashes})`, so every constraint's root
+/// differs from the bare `{tx}` container relays verified before, even without blobs
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
 pub struct Constraint {
     pub tx: [u8; 32],
+    #[serde(default)]
+    pub blob_versioned_hashes: Vec<B256>,
+}
+
+/// Most blob hashes a constraint commits to, `MAX_BLOB_COMMITMENTS_PER_BLOCK`
+type MaxBlobsPerConstraint = U4096;
+
+/// The container a `Constraint` is hashed as
+#[derive(TreeHash)]
+struct ConstraintContainer {
+    tx: [u8; 32],
+    blob_versioned_hashes: VariableList<[u8; 32], MaxBlobsPerConstraint>,
+}
+
+impl Constraint {
+    pub fn is_blob(&self) -> bool {
+        !self.blob_versioned_hashes.is_empty()
+    }
+}
+
+impl TreeHash for Constraint {
+    fn tree_hash_type() -> TreeHashType {
+        TreeHashType::Container
+    }
+
+    fn tree_hash_packed_encoding(&self) -> PackedEncoding {
+        unreachable!("Constraint should never be packed")
+    }
+
+    fn tree_hash_packing_factor() -> usize {
+        unreachable!("Constraint should never be packed")
+    }
+
+    fn tree_hash_root(&self) -> Hash256 {
+        ConstraintContainer {
+            tx: self.tx,
+            blob_versioned_hashes: VariableList::from(
+                self.blob_versioned_hashes.iter().map(|hash| hash.0).collect::<Vec<_>>(),
+            ),
+        }
+        .tree_hash_root()
+    }
 }
 
 impl Serialize for Constraint {
@@ -51,29 +183,67 @@ impl Serialize for Constraint {
     where
         S: Serializer,
     {
-        let mut item = serializer.serialize_struct("Constraint", 1)?;
+        let mut item = serializer.serialize_struct("Constraint", 2)?;
         item.serialize_field("tx", &self.tx.encode_hex())?;
+        if self.is_blob() {
+            item.serialize_field("blob_versioned_hashes", &self.blob_versioned_hashes)?;
+        } else {
+            item.skip_field("blob_versioned_hashes")?;
+        }
         item.end()
     }
 }
 
 impl InclusionList {
+    /// Constraints beyond `MAX_CONSTRAINTS_PER_LIST` are cut off
     pub fn new(slot: u64, validator_index: usize, transactions: Vec<Constraint>) -> Self {
-       
         let list_of_lists = vec![transactions.into()];
 
         Self {
             slot,
             validator_index,
+            inclusion_list_type: InclusionListType::Spot as u8,
+            conditional: false,
+            revision: 0,
             constraints: list_of_lists.into(),
         }
     }
+
+    pub fn with_type(mut self, il_type: InclusionListType) -> Self {
+        self.inclusion_list_type = il_type as u8;
+        self
+    }
+
+    pub fn with_conditional(mut self, conditional: bool) -> Self {
+        self.conditional = conditional;
+        self
+    }
+
+    pub fn with_revision(mut self, revision: u64) -> Self {
+        self.revision = revision;
+        self
+    }
+
+    pub fn il_type(&self) -> InclusionListType {
+        self.inclusion_list_type.into()
+    }
+
+    /// A list for the same slot, signer, type, condition and revision with different constraints
+    pub fn with_constraints(&self, constraints: Vec<Constraint>) -> Self {
+        Self::new(self.slot, self.validator_index, constraints)
+            .with_type(self.il_type())
+            .with_conditional(self.conditional)
+            .with_revision(self.revision)
+    }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct InclusionRequest {
     pub message: InclusionList,
     pub signature: BlsSignature,
+    /// Validator's delegation to the proxy key that produced `signature`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_delegation: Option<SignedProxyDelegation>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -90,7 +260,7 @@ impl InclusionProof {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default, Clone)]
 pub struct Transaction {
     pub is_eip4844: bool,
     pub gas_limit: u128,
@@ -99,6 +269,124 @@ pub struct Transaction {
     pub tx_hash: B256,
     pub bytes: Bytes,
     pub index: Option<u64>,
+    /// The EIP-2718 encoded signed transaction, when known
+    pub raw: Option<Bytes>,
+    pub from: Address,
+    pub nonce: u64,
+    /// `gas_price` for transactions that predate EIP-1559
+    pub max_fee_per_gas: Option<u128>,
+    pub value: U256,
+    /// `None` for contract creations
+    pub to: Option<Address>,
+    /// EIP-2718 transaction type
+    pub tx_type: u8,
+    pub max_fee_per_blob_gas: Option<u128>,
+    /// Empty for anything but EIP-4844 transactions
+    pub blob_versioned_hashes: Vec<B256>,
+}
+
+impl Transaction {
+    /// Decode an EIP-2718 encoded signed transaction and recover its sender
+    pub fn from_raw(raw: Bytes) -> Result<Self, InclusionListBoostError> {
+        let envelope = TxEnvelope::decode_2718(&mut raw.as_ref())?;
+
+        let transaction = match &envelope {
+            TxEnvelope::Legacy(signed) => Self {
+                from: signed.recover_signer()?,
+                to: signed.tx().to.to().copied(),
+                nonce: signed.tx().nonce,
+                gas: signed.tx().gas_limit,
+                max_fee_per_gas: Some(signed.tx().gas_price),
+                value: signed.tx().value,
+                bytes: signed.tx().input.clone(),
+                ..Default::default()
+            },
+            TxEnvelope::Eip2930(signed) => Self {
+                tx_type: 1,
+                from: signed.recover_signer()?,
+                to: signed.tx().to.to().copied(),
+                nonce: signed.tx().nonce,
+                gas: signed.tx().gas_limit,
+                max_fee_per_gas: Some(signed.tx().gas_price),
+                value: signed.tx().value,
+                bytes: signed.tx().input.clone(),
+                ..Default::default()
+            },
+            TxEnvelope::Eip1559(signed) => Self {
+                tx_type: 2,
+                from: signed.recover_signer()?,
+                to: signed.tx().to.to().copied(),
+                nonce: signed.tx().nonce,
+                gas: signed.tx().gas_limit,
+                max_fee_per_gas: Some(signed.tx().max_fee_per_gas),
+                max_priority_fee_per_gas: Some(signed.tx().max_priority_fee_per_gas),
+                value: signed.tx().value,
+                bytes: signed.tx().input.clone(),
+                ..Default::default()
+            },
+            TxEnvelope::Eip4844(signed) => {
+                let tx = signed.tx().tx();
+                Self {
+                    is_eip4844: true,
+                    tx_type: 3,
+                    from: signed.recover_signer()?,
+                    to: Some(tx.to),
+                    nonce: tx.nonce,
+                    gas: tx.gas_limit,
+                    max_fee_per_gas: Some(tx.max_fee_per_gas),
+                    max_priority_fee_per_gas: Some(tx.max_priority_fee_per_gas),
+                    max_fee_per_blob_gas: Some(tx.max_fee_per_blob_gas),
+                    blob_versioned_hashes: tx.blob_versioned_hashes.clone(),
+                    value: tx.value,
+                    bytes: tx.input.clone(),
+                    ..Default::default()
+                }
+            }
+            _ => return Err("unsupported transaction type".to_string().into()),
+        };
+
+        Ok(Self {
+            tx_hash: *envelope.tx_hash(),
+            gas_limit: transaction.gas,
+            raw: Some(raw),
+            ..transaction
+        })
+    }
+
+    pub fn to_constraint(&self) -> Constraint {
+        Constraint {
+            tx: bytes_to_array(self.bytes.clone()),
+            blob_versioned_hashes: self.blob_versioned_hashes.clone(),
+        }
+    }
+
+    /// Blob gas used by the transaction's blobs
+    pub fn blob_gas(&self) -> u128 {
+        self.blob_versioned_hashes.len() as u128 * DATA_GAS_PER_BLOB
+    }
+}
+
+/// A constraint together with the hash of the transaction it was built from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ListedTransaction {
+    pub tx_hash: B256,
+    /// EIP-2718 encoding, needed for lists that carry whole transactions
+    #[serde(skip)]
+    pub raw: Option<Bytes>,
+    /// Gas limit of the transaction, which conditional lists compare to a block's remaining gas
+    pub gas: u128,
+    pub constraint: Constraint,
+}
+
+impl From<&Transaction> for ListedTransaction {
+    fn from(value: &Transaction) -> Self {
+        Self {
+            tx_hash: value.tx_hash,
+            raw: value.raw.clone(),
+            gas: value.gas,
+            constraint: value.to_constraint(),
+        }
+    }
 }
 
 impl From<Arc<ValidPoolTransaction<MockTransaction>>> for Transaction {
@@ -111,25 +399,243 @@ impl From<Arc<ValidPoolTransaction<MockTransaction>>> for Transaction {
             max_priority_fee_per_gas: Some(value.priority_fee_or_price()),
             bytes: value.transaction.get_input().into(),
             index: None,
+            raw: None,
+            from: value.transaction.sender(),
+            nonce: value.transaction.nonce(),
+            max_fee_per_gas: Some(value.transaction.max_fee_per_gas()),
+            value: value.transaction.value(),
+            to: value.transaction.to(),
+            tx_type: value.transaction.tx_type(),
+            max_fee_per_blob_gas: value.transaction.max_fee_per_blob_gas(),
+            blob_versioned_hashes: vec![],
         }
     }
 }
 
 impl From<alloy::rpc::types::Transaction> for Transaction {
     fn from(value: alloy::rpc::types::Transaction) -> Self {
+        Self::from_rpc(value, false)
+    }
+}
+
+impl Transaction {
+    /// Convert a transaction from the execution node. With `encode_raw` the signed transaction is
+    /// re-encoded, which only lists carrying raw bytes need
+    pub fn from_rpc(value: alloy::rpc::types::Transaction, encode_raw: bool) -> Self {
+        let raw = encode_raw
+            .then(|| TxEnvelope::try_from(value.clone()).ok())
+            .flatten()
+            .map(|envelope| envelope.encoded_2718().into());
+
         Self {
             tx_hash: value.tx_hash(),
-            is_eip4844: false,
+            is_eip4844: value.transaction_type == Some(3),
             gas: value.gas,
             gas_limit: value.gas,
             max_priority_fee_per_gas: value.max_priority_fee_per_gas,
             bytes: value.input,
             index: value.transaction_index,
+            raw,
+            from: value.from,
+            nonce: value.nonce,
+            max_fee_per_gas: value.max_fee_per_gas.or(value.gas_price),
+            value: value.value,
+            to: value.to,
+            tx_type: value.transaction_type.unwrap_or_default(),
+            max_fee_per_blob_gas: value.max_fee_per_blob_gas,
+            blob_versioned_hashes: value.blob_versioned_hashes.unwrap_or_default(),
         }
     }
 }
 
+/// Why a pending transaction was left out of an inclusion list
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum DropReason {
+    DeniedSender,
+    SenderNotAllowed,
+    DeniedContract,
+    ContractNotAllowed,
+    SenderLimit { limit: usize },
+    TipTooLow { min: u128 },
+    CalldataTooLarge { size: usize, max: usize },
+    ExcludedType { tx_type: u8 },
+    BaseFeeNotCovered { max_fee_per_gas: u128, base_fee: u128 },
+    BlobBaseFeeNotCovered { max_fee_per_blob_gas: u128, blob_base_fee: u128 },
+    StaleNonce { account_nonce: u64 },
+    NonceGap { expected_nonce: u64 },
+    DuplicateNonce,
+    NonceLookupFailed { error: String },
+    SimulationFailed { error: String },
+    Reverted,
+}
+
+/// A pending transaction that did not make it into the inclusion list
+#[derive(Debug, Clone, Serialize)]
+pub struct DroppedCandidate {
+    pub tx_hash: B256,
+    #[serde(flatten)]
+    pub reason: DropReason,
+}
+
+/// The latest inclusion list built for a slot and list type, along with the transactions behind
+/// its constraints. Spot and forward lists for the same slot are cached side by side
+#[derive(Debug, Clone, Serialize)]
+pub struct CachedInclusionList {
+    pub inclusion_list: InclusionList,
+    pub transactions: Vec<ListedTransaction>,
+    /// A frozen list is submitted as is and never rebuilt by the sidecar
+    pub frozen: bool,
+    /// Candidates that were left out and why
+    pub dropped: Vec<DroppedCandidate>,
+    /// Gas used by the listed transactions when simulated against the head state
+    pub simulated_gas_used: Option<u128>,
+    /// Censorship evidence of the mempool derived transactions, keyed by hash
+    pub evidence: HashMap<B256, CensorshipEvidence>,
+    /// Candidates that touch sanctioned addresses, whether they were listed or not
+    pub sanctions_tags: HashMap<B256, Vec<SanctionsTag>>,
+    /// Vantage points that listed each mempool derived transaction, empty without vantage points
+    pub sources: HashMap<B256, Vec<String>>,
+    /// Whether the slot's block satisfied the list, once it was checked
+    pub satisfaction: Option<Satisfaction>,
+}
+
+impl InclusionList {
+    /// Whether this revision would tell the relay nothing new over the `submitted` list
+    pub fn unchanged_from(&self, submitted: &InclusionList) -> bool {
+        submitted.constraints == self.constraints && submitted.conditional == self.conditional
+    }
+}
+
+impl CachedInclusionList {
+    /// `inclusion_list` provides the slot, signer, type and condition, its constraints
+    /// are replaced by the ones of `transactions`
+    pub fn new(inclusion_list: InclusionList, transactions: Vec<ListedTransaction>, frozen: bool) -> Self {
+        let mut cached = Self {
+            inclusion_list,
+            transactions,
+            frozen,
+            dropped: vec![],
+            simulated_gas_used: None,
+            evidence: HashMap::new(),
+            sanctions_tags: HashMap::new(),
+            sources: HashMap::new(),
+            satisfaction: None,
+        };
+        cached.rebuild();
+        cached
+    }
+
+    /// Recompute `inclusion_list` after `transactions` changed, so the signed constraints are
+    /// always exactly the ones of `transactions`
+    fn rebuild(&mut self) {
+        if self.transactions.len() > MAX_CONSTRAINTS_PER_LIST {
+            tracing::warn!(
+                transaction_count = self.transactions.len(),
+                max = MAX_CONSTRAINTS_PER_LIST,
+                "Inclusion list is too long, dropping the last transactions"
+            );
+            self.transactions.truncate(MAX_CONSTRAINTS_PER_LIST);
+        }
+        let constraints = self.transactions.iter().map(|tx| tx.constraint.clone()).collect();
+        self.inclusion_list = self.inclusion_list.with_constraints(constraints);
+    }
+}
+
+/// Lists are kept per slot and type, in `both` mode a slot has a spot and a forward list
+/// signed by different proposers
+pub type ListKey = (u64, InclusionListType);
+
+/// Operator overrides applied when building the inclusion list for a slot
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SlotOverrides {
+    /// Always included, ahead of any mempool derived constraint
+    pub pinned: Vec<ListedTransaction>,
+    /// Never included
+    pub removed: HashSet<B256>,
+    pub frozen: bool,
+}
+
 pub struct InclusionBoostCache {
-    pub block_cache: Arc<RwLock<HashMap<u64, Vec<B256>>>>,
-    pub inclusion_list_cache: Arc<RwLock<HashMap<u64, InclusionList>>>,
+    /// Recent blocks the censorship score compares pending transactions against
+    pub block_cache: Arc<RwLock<BlockHistory>>,
+    pub inclusion_list_cache: Arc<RwLock<HashMap<ListKey, CachedInclusionList>>>,
+    pub overrides: Arc<RwLock<HashMap<ListKey, SlotOverrides>>>,
+    /// Last list the relay accepted, revisions are compared against it rather than the latest build
+    pub submitted: Arc<RwLock<HashMap<ListKey, InclusionList>>>,
+    pub inclusion_requests: Arc<InclusionRequestQueue>,
+    /// Unix time at which each pending transaction was first seen in the mempool
+    pub first_seen: Arc<RwLock<HashMap<B256, u64>>>,
+    /// Delegations sent to relays and the ones waiting to be revoked
+    pub delegations: Arc<RwLock<DelegationManager>>,
+    /// Inclusion list and delegation roots signed per validator and slot
+    pub protection: Arc<RwLock<ProtectionDb>>,
+}
+
+impl InclusionBoostCache {
+    /// Pin a transaction for the list `key` and add it to the cached list, if any
+    pub fn pin_transaction(&self, key: ListKey, transaction: ListedTransaction) {
+        let mut overrides = self.overrides.write();
+        let slot_overrides = overrides.entry(key).or_default();
+        slot_overrides.removed.remove(&transaction.tx_hash);
+        if !slot_overrides.pinned.iter().any(|tx| tx.tx_hash == transaction.tx_hash) {
+            slot_overrides.pinned.push(transaction.clone());
+        }
+
+        if let Some(cached) = self.inclusion_list_cache.write().get_mut(&key) {
+            cached.transactions.retain(|tx| tx.tx_hash != transaction.tx_hash);
+            let position = cached
+                .transactions
+                .iter()
+                .position(|tx| !slot_overrides.pinned.iter().any(|pinned| pinned.tx_hash == tx.tx_hash))
+                .unwrap_or(cached.transactions.len());
+            cached.transactions.insert(position, transaction);
+            cached.rebuild();
+        }
+    }
+
+    /// Exclude a transaction from the list `key` and drop it from the cached list, if any
+    pub fn remove_transaction(&self, key: ListKey, tx_hash: B256) {
+        let mut overrides = self.overrides.write();
+        let slot_overrides = overrides.entry(key).or_default();
+        slot_overrides.pinned.retain(|tx| tx.tx_hash != tx_hash);
+        slot_overrides.removed.insert(tx_hash);
+
+        if let Some(cached) = self.inclusion_list_cache.write().get_mut(&key) {
+            cached.transactions.retain(|tx| tx.tx_hash != tx_hash);
+            cached.rebuild();
+        }
+    }
+
+    /// Stop the sidecar from rebuilding the list `key`
+    pub fn set_frozen(&self, key: ListKey, frozen: bool) {
+        self.overrides.write().entry(key).or_default().frozen = frozen;
+
+        if let Some(cached) = self.inclusion_list_cache.write().get_mut(&key) {
+            cached.frozen = frozen;
+        }
+    }
+
+    /// Forget cached lists, overrides and delegations for slots before `slot`
+    pub fn prune(&self, slot: u64) {
+        self.inclusion_list_cache.write().retain(|(s, _), _| *s >= slot);
+        self.overrides.write().retain(|(s, _), _| *s >= slot);
+        self.submitted.write().retain(|(s, _), _| *s >= slot);
+        self.delegations.write().prune(slot);
+        self.protection.write().prune(slot);
+    }
+
+    /// Record when `pending` transactions were first seen, forgetting the ones that left the mempool
+    pub fn track_pending(&self, pending: &HashSet<B256>, now: u64) {
+        let mut first_seen = self.first_seen.write();
+        first_seen.retain(|tx_hash, _| pending.contains(tx_hash));
+        for tx_hash in pending {
+            first_seen.entry(*tx_hash).or_insert(now);
+        }
+    }
+
+    /// Unix time `tx_hash` was first seen pending
+    pub fn first_seen(&self, tx_hash: &B256) -> Option<u64> {
+        self.first_seen.read().get(tx_hash).copied()
+    }
 }
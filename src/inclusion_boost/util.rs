@@ -0,0 +1,13 @@
+use std::fs;
+
+use serde::Serialize;
+
+use super::error::InclusionListBoostError;
+
+/// Replace the file at `path` without leaving a partially written file behind
+pub fn write_json(path: &str, value: &impl Serialize) -> Result<(), InclusionListBoostError> {
+    let tmp_path = format!("{path}.tmp");
+    fs::write(&tmp_path, serde_json::to_string_pretty(value)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
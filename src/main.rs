@@ -9,21 +9,34 @@ use config::InclusionListConfig;
 use serde::Deserialize;
 
 use inclusion_boost::{
-    error::InclusionListBoostError, sidecar::InclusionSideCar, types::InclusionBoostCache,
+    delegation::DelegationManager,
+    mempool::MempoolSources,
+    error::InclusionListBoostError,
+    history::BlockHistory,
+    inclusion_requests::InclusionRequestQueue,
+    policy::InclusionPolicy,
+    protection::ProtectionDb,
+    sanctions::SanctionsList,
+    sidecar::InclusionSideCar,
+    supervisor::{supervise, SidecarHealth},
+    types::{InclusionBoostCache, InclusionListFormat},
 };
 use types::MainConfig;
 
-use crate::pbs::InclusionBoostApi;
-use alloy::{
-    providers::{ProviderBuilder, RootProvider},
-    transports::http::Http,
+use crate::{
+    api::{inclusion_request::RateLimiter, run_api_server, ApiState},
+    pbs::InclusionBoostApi,
+    status::ModuleStatus,
 };
 use parking_lot::RwLock;
+use tokio::sync::Mutex;
 
+mod api;
 mod config;
 mod inclusion_boost;
 mod lookahead;
 mod pbs;
+mod status;
 mod test;
 mod types;
 
@@ -32,31 +45,94 @@ async fn main() -> Result<(), InclusionListBoostError> {
     // parse_toml();
     let config = load_commit_module_config::<InclusionListConfig>().expect("failed to load config");
     let _ = initialize_tracing_log(&config.id);
+    assert!(
+        !config.extra.use_proxy_keys || config.extra.proxy_delegations_file.is_some(),
+        "use_proxy_keys requires proxy_delegations_file"
+    );
   
-    let eth_provider: RootProvider<Http<reqwest::Client>> =
-        ProviderBuilder::new().on_http(config.extra.execution_api.parse().unwrap());
+    let mempool = Arc::new(
+        MempoolSources::new(
+            &config.extra.execution_api,
+            config.extra.mempool_aggregation,
+            config.extra.inclusion_list_format == InclusionListFormat::Focil,
+        )
+        .expect("failed to load execution nodes"),
+    );
     let cache = Arc::new(InclusionBoostCache {
-        block_cache: Arc::new(RwLock::new(HashMap::new())),
+        block_cache: Arc::new(RwLock::new(BlockHistory::new(config.extra.censorship_window_blocks))),
         inclusion_list_cache: Arc::new(RwLock::new(HashMap::new())),
+        overrides: Arc::new(RwLock::new(HashMap::new())),
+        submitted: Arc::new(RwLock::new(HashMap::new())),
+        inclusion_requests: Arc::new(InclusionRequestQueue::new(
+            config.extra.inclusion_request_queue_size,
+        )),
+        first_seen: Arc::new(RwLock::new(HashMap::new())),
+        delegations: Arc::new(RwLock::new(
+            DelegationManager::load(config.extra.delegation_state_file.clone())
+                .expect("failed to load delegation state"),
+        )),
+        protection: Arc::new(RwLock::new(
+            ProtectionDb::load(config.extra.protection_db_file.clone())
+                .expect("failed to load signing protection database"),
+        )),
     });
 
     let (pbs_module, pbs_module_custom_data) = load_pbs_custom_config::<InclusionListConfig>().expect("failed to load pbs config");
 
     let state = PbsState::new(pbs_module).with_data(pbs_module_custom_data);
 
-    let mut inclusion_sidecar =
-        InclusionSideCar::new(config, eth_provider, cache);
+    let health = Arc::new(SidecarHealth::default());
+    let status = Arc::new(ModuleStatus::new(health.clone()));
+
+    let api_state = ApiState {
+        status: status.clone(),
+        beacon_api: config.extra.beacon_api.clone(),
+        mempool: mempool.clone(),
+        cache: cache.clone(),
+        admin_token: config.extra.admin_token.clone(),
+        rate_limiter: Arc::new(RateLimiter::new(config.extra.inclusion_requests_per_minute)),
+    };
+    let api_port = config.extra.status_port;
+
+    let policy = config
+        .extra
+        .policy_file
+        .as_deref()
+        .map(InclusionPolicy::load)
+        .transpose()
+        .expect("failed to load inclusion policy")
+        .unwrap_or_default();
+
+    let sanctions = config
+        .extra
+        .sanctions_file
+        .as_deref()
+        .map(SanctionsList::load)
+        .transpose()
+        .expect("failed to load sanctions list");
+
+    let inclusion_sidecar = Arc::new(Mutex::new(InclusionSideCar::new(
+        config,
+        mempool,
+        cache,
+        status,
+        policy,
+        sanctions,
+    )));
 
     let pbs_server = tokio::spawn(async move {
         let _ = PbsService::run::<InclusionListConfig, InclusionBoostApi>(state).await;
     });
 
-    let il_sidecar = tokio::spawn(async move {
-        let _ = inclusion_sidecar.run().await;
-    });
+    let il_sidecar = tokio::spawn(supervise(inclusion_sidecar, health));
 
+    let api_server = tokio::spawn(async move {
+        if let Err(err) = run_api_server(api_port, api_state).await {
+            tracing::error!(?err, "Status API stopped");
+        }
+    });
 
-    let _ = tokio::join!(pbs_server, il_sidecar);
+    let _ = tokio::join!(pbs_server, il_sidecar, api_server);
 
     Ok(())
 }
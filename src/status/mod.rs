@@ -0,0 +1,110 @@
+use std::{collections::HashMap, sync::Arc};
+
+use parking_lot::RwLock;
+use serde::Serialize;
+
+use alloy::primitives::B256;
+
+use crate::inclusion_boost::{
+    history::{BlockRecord, BuilderSkipReport},
+    mempool::SourceHealth,
+    satisfaction::Satisfaction,
+    selection::CensorshipEvidence,
+    supervisor::SidecarHealth,
+    types::InclusionListType,
+};
+
+/// A slot in the lookahead that one of our validator keys will propose
+#[derive(Debug, Clone, Serialize)]
+pub struct UpcomingProposal {
+    pub slot: u64,
+    pub validator_index: usize,
+}
+
+/// Outcome of posting an inclusion list (or delegation) to a single relay
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SubmissionResult {
+    Accepted,
+    Rejected,
+    Failed { error: String },
+}
+
+/// The last inclusion list the sidecar built
+#[derive(Debug, Clone, Serialize)]
+pub struct InclusionListReport {
+    pub slot: u64,
+    pub validator_index: usize,
+    pub inclusion_list_type: InclusionListType,
+    pub revision: u64,
+    pub constraints: usize,
+    pub simulated_gas_used: Option<u128>,
+    /// Blocks skipped, time pending and tip of each mempool derived transaction
+    pub censorship_evidence: HashMap<B256, CensorshipEvidence>,
+    /// Vantage points that listed each mempool derived transaction
+    pub sources: HashMap<B256, Vec<String>>,
+    /// Submission result keyed by relay url
+    pub submissions: HashMap<String, SubmissionResult>,
+}
+
+/// Whether the block of a slot we published a list for satisfied it
+#[derive(Debug, Clone, Serialize)]
+pub struct SatisfactionReport {
+    pub slot: u64,
+    pub inclusion_list_type: InclusionListType,
+    pub conditional: bool,
+    pub satisfaction: Satisfaction,
+}
+
+/// Result of delegating inclusion list authority for the proposers of an epoch
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DelegationReport {
+    pub epoch: u64,
+    pub succeeded: Vec<u64>,
+    pub failed: Vec<u64>,
+    /// Delegations that were already sent and left alone
+    pub already_delegated: usize,
+    /// Slots whose delegation was revoked
+    pub revoked: Vec<u64>,
+}
+
+/// Everything the sidecar reports about its own progress
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SidecarStatus {
+    pub last_head_slot: Option<u64>,
+    pub validator_keys: usize,
+    pub upcoming_proposals: Vec<UpcomingProposal>,
+    pub last_inclusion_list: Option<InclusionListReport>,
+    pub last_satisfaction: Option<SatisfactionReport>,
+    pub next_epoch_delegations: Option<DelegationReport>,
+    /// Blocks in the censorship window
+    pub block_history: Vec<BlockRecord>,
+    /// Skipped transactions per builder over the censorship window
+    pub builder_skips: Vec<BuilderSkipReport>,
+    /// Health of every execution node the mempool is read from
+    pub mempool_sources: Vec<SourceHealth>,
+}
+
+/// Shared view of the module's state, written by the sidecar and read by the status API
+#[derive(Debug)]
+pub struct ModuleStatus {
+    pub health: Arc<SidecarHealth>,
+    sidecar: RwLock<SidecarStatus>,
+}
+
+impl ModuleStatus {
+    pub fn new(health: Arc<SidecarHealth>) -> Self {
+        Self {
+            health,
+            sidecar: RwLock::new(SidecarStatus::default()),
+        }
+    }
+
+    pub fn update(&self, f: impl FnOnce(&mut SidecarStatus)) {
+        f(&mut self.sidecar.write());
+    }
+
+    pub fn snapshot(&self) -> SidecarStatus {
+        self.sidecar.read().clone()
+    }
+}
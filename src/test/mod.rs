@@ -1,20 +1,63 @@
 mod test {
 
-    use alloy::{primitives::Bytes, rpc::types::Block};
+    use alloy::{
+        consensus::{SignableTransaction, TxEip4844, TxEnvelope},
+        eips::eip2718::Encodable2718,
+        network::TxSignerSync,
+        primitives::{Address, Bytes, B256, U256},
+        rpc::types::{beacon::BlsPublicKey, Block, Header},
+        signers::local::PrivateKeySigner,
+    };
     use axum::{
+        extract::{ConnectInfo, State},
         response::IntoResponse,
         routing::{post, IntoMakeService},
         Json, Router,
     };
-    use cb_common::commit::client::SignerClient;
+    use cb_common::commit::{client::SignerClient, request::SignedProxyDelegation};
     use reqwest::StatusCode;
     use reth_transaction_pool::{test_utils::{MockTransactionFactory, TestPoolBuilder}, TransactionOrigin, TransactionPool};
-    use std::{collections::HashMap, net::SocketAddr};
+    use std::{
+        collections::{HashMap, HashSet},
+        net::SocketAddr,
+    };
     use tokio::net::TcpListener;
+    use ssz_types::{typenum::U4096, VariableList};
+    use tree_hash::TreeHash;
 
 
+    use crate::api::{
+        inclusion_request::{handle_post_inclusion_request, RateLimiter},
+        ApiState,
+    };
+    use crate::config::InclusionListConfig;
     use crate::inclusion_boost::{
-        types::{InclusionList, Transaction},
+        aggregation::{Aggregation, Committee},
+        clock::SlotClock,
+        delegation::{Delegation, DelegationManager},
+        error::InclusionListBoostError,
+        fees::{next_base_fee, BlobParams, BlobSchedule, NextBlockFees, DATA_GAS_PER_BLOB},
+        focil::{
+            compute_domain, DomainInputs, FocilInclusionList, DOMAIN_INCLUSION_LIST_COMMITTEE,
+            MAX_BYTES_PER_INCLUSION_LIST,
+        },
+        history::{BlockHistory, BlockRecord},
+        mempool::MempoolSources,
+        inclusion_requests::InclusionRequestQueue,
+        nonces::{lookup_nonces, order_by_nonce, order_sender_transactions, order_with_nonces, AccountNonces},
+        policy::InclusionPolicy,
+        protection::ProtectionDb,
+        proxy::reusable_delegations,
+        sidecar::{resolve_head_block_number, select_list_targets, ListTarget, RevisionSchedule, MAX_MISSED_SLOTS},
+        sanctions::{Exposure, SanctionsList, SanctionsTag},
+        satisfaction::{check_block, check_header, Satisfaction},
+        selection::{select, Candidate, CensorshipEvidence},
+        simulation::Simulator,
+        supervisor::{restart_loop, SidecarHealth},
+        types::{
+            CachedInclusionList, Constraint, DropReason, InclusionBoostCache, InclusionList, InclusionListMode,
+            InclusionListType, ListedTransaction, Transaction, MAX_CONSTRAINTS_PER_LIST,
+        },
         InclusionBoost,
     };
     const ID: &str = "IL_COMMIT";
@@ -103,4 +146,1098 @@ mod test {
 
         assert_eq!(response, Some(()))
     }
+
+    /// Transaction `nonce` of the sender with address `sender`, with a hash unique to both
+    fn tx(sender: u8, nonce: u64) -> Transaction {
+        Transaction {
+            from: Address::with_last_byte(sender),
+            nonce,
+            tx_hash: B256::with_last_byte(sender * 10 + nonce as u8),
+            ..Default::default()
+        }
+    }
+
+    fn empty_cache() -> InclusionBoostCache {
+        InclusionBoostCache {
+            block_cache: std::sync::Arc::new(parking_lot::RwLock::new(BlockHistory::new(1))),
+            inclusion_list_cache: Default::default(),
+            overrides: Default::default(),
+            submitted: Default::default(),
+            inclusion_requests: std::sync::Arc::new(InclusionRequestQueue::new(1)),
+            first_seen: Default::default(),
+            delegations: Default::default(),
+            protection: Default::default(),
+        }
+    }
+
+    /// Execution node answering every JSON-RPC method with its entry in `results`
+    async fn mock_rpc(results: serde_json::Value) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<serde_json::Value>| {
+                let result = request["method"].as_str().map(|method| results[method].clone()).unwrap_or_default();
+                async move { Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })) }
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        url
+    }
+
+    fn api_state(execution_api: String, requests_per_minute: u32) -> ApiState {
+        ApiState {
+            status: std::sync::Arc::new(crate::status::ModuleStatus::new(Default::default())),
+            beacon_api: String::new(),
+            mempool: std::sync::Arc::new(MempoolSources::new(&[execution_api], Aggregation::Union, false).unwrap()),
+            cache: std::sync::Arc::new(empty_cache()),
+            admin_token: None,
+            rate_limiter: std::sync::Arc::new(RateLimiter::new(requests_per_minute)),
+        }
+    }
+
+    async fn post_inclusion_request(state: &ApiState, client: [u8; 4], tx: Bytes) -> (StatusCode, String) {
+        let response = handle_post_inclusion_request(
+            State(state.clone()),
+            ConnectInfo(SocketAddr::from((client, 9000))),
+            Json(serde_json::from_value(serde_json::json!({ "tx": tx })).unwrap()),
+        )
+        .await;
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    #[tokio::test]
+    pub async fn inclusion_request_rate_limit() {
+        let state = api_state(mock_rpc(serde_json::json!({})).await, 1);
+
+        // Undecodable requests count towards the limit, which is kept per client
+        let (status, _) = post_inclusion_request(&state, [10, 0, 0, 1], Bytes::from_static(&[0x01])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = post_inclusion_request(&state, [10, 0, 0, 1], Bytes::from_static(&[0x01])).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        let (status, _) = post_inclusion_request(&state, [10, 0, 0, 2], Bytes::from_static(&[0x01])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    pub async fn inclusion_request_validation() {
+        let signer = PrivateKeySigner::random();
+        let sign = |nonce: u64| {
+            let mut tx = TxEip4844 {
+                chain_id: 1,
+                nonce,
+                gas_limit: 21_000,
+                max_fee_per_gas: 10,
+                max_priority_fee_per_gas: 1,
+                max_fee_per_blob_gas: 1_000,
+                blob_versioned_hashes: vec![B256::repeat_byte(0x01)],
+                ..Default::default()
+            };
+            let signature = signer.sign_transaction_sync(&mut tx).unwrap();
+            Bytes::from(TxEnvelope::from(tx.into_signed(signature)).encoded_2718())
+        };
+        let node = |balance: u128| {
+            mock_rpc(serde_json::json!({
+                "eth_getTransactionCount": "0x1",
+                "eth_getBlockByNumber": null,
+                "eth_getBalance": format!("{:#x}", U256::from(balance)),
+            }))
+        };
+        let gas_fee = 21_000 * 10;
+        let blob_fee = DATA_GAS_PER_BLOB * 1_000;
+
+        // The balance has to cover the blob gas next to the gas
+        let state = api_state(node(gas_fee).await, 10);
+        let (status, body) = post_inclusion_request(&state, [10, 0, 0, 1], sign(1)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("does not cover"), "{body}");
+
+        let state = api_state(node(gas_fee + blob_fee).await, 10);
+        let (status, _) = post_inclusion_request(&state, [10, 0, 0, 1], sign(0)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = post_inclusion_request(&state, [10, 0, 0, 1], sign(2)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = post_inclusion_request(&state, [10, 0, 0, 1], sign(1)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(state.cache.inclusion_requests.transactions().len(), 1);
+        assert!(state.cache.inclusion_requests.transactions()[0].blob_gas() > 0);
+
+        // A queued transaction isn't queued twice
+        let (status, body) = post_inclusion_request(&state, [10, 0, 0, 1], sign(1)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("already queued"), "{body}");
+    }
+
+    #[test]
+    pub fn inclusion_policy() {
+        let call = |sender: u8, nonce: u64| Transaction {
+            to: Some(Address::with_last_byte(100)),
+            tx_type: 2,
+            max_priority_fee_per_gas: Some(2_000_000_000),
+            ..tx(sender, nonce)
+        };
+        let reasons = |policy: &InclusionPolicy, transactions: Vec<Transaction>| {
+            policy.apply(transactions).1.into_iter().map(|dropped| dropped.reason).collect::<Vec<_>>()
+        };
+
+        let policy = InclusionPolicy {
+            deny_senders: HashSet::from([Address::with_last_byte(1)]),
+            deny_contracts: HashSet::from([Address::with_last_byte(101)]),
+            min_priority_fee_per_gas: Some(1_000_000_000),
+            max_calldata_size: Some(4),
+            excluded_tx_types: HashSet::from([3]),
+            ..Default::default()
+        };
+        let (accepted, dropped) = policy.apply(vec![call(2, 0)]);
+        assert_eq!((accepted.len(), dropped.len()), (1, 0));
+
+        // Senders are only matched on `from`, contracts only on `to`
+        let to_denied_sender = Transaction {
+            to: Some(Address::with_last_byte(1)),
+            ..call(2, 1)
+        };
+        let to_denied_contract = Transaction {
+            to: Some(Address::with_last_byte(101)),
+            ..call(3, 0)
+        };
+        let from_denied_contract = Transaction {
+            from: Address::with_last_byte(101),
+            ..call(4, 0)
+        };
+        assert_eq!(
+            reasons(&policy, vec![call(1, 0), to_denied_sender, to_denied_contract, from_denied_contract]),
+            vec![DropReason::DeniedSender, DropReason::DeniedContract]
+        );
+
+        let low_tip = Transaction {
+            max_priority_fee_per_gas: Some(1),
+            ..call(2, 0)
+        };
+        let large_calldata = Transaction {
+            bytes: Bytes::from(vec![0; 5]),
+            ..call(3, 0)
+        };
+        let blob = Transaction {
+            tx_type: 3,
+            ..call(4, 0)
+        };
+        assert_eq!(reasons(&policy, vec![low_tip, large_calldata, blob]), vec![
+            DropReason::TipTooLow { min: 1_000_000_000 },
+            DropReason::CalldataTooLarge { size: 5, max: 4 },
+            DropReason::ExcludedType { tx_type: 3 },
+        ]);
+
+        // Allow lists reject everything else, contract creations included
+        let policy = InclusionPolicy {
+            allow_senders: HashSet::from([Address::with_last_byte(2)]),
+            allow_contracts: HashSet::from([Address::with_last_byte(100)]),
+            ..Default::default()
+        };
+        let creation = Transaction { to: None, ..call(2, 1) };
+        assert_eq!(reasons(&policy, vec![call(2, 0), call(3, 0), creation]), vec![
+            DropReason::SenderNotAllowed,
+            DropReason::ContractNotAllowed,
+        ]);
+    }
+
+    #[test]
+    pub fn sender_limit_keeps_lowest_nonces() {
+        let policy = InclusionPolicy {
+            max_constraints_per_sender: Some(2),
+            ..Default::default()
+        };
+
+        // The limit is counted on nonces, not on the order the mempool returned them in
+        let (accepted, dropped) = policy.limit_per_sender(vec![tx(1, 7), tx(2, 0), tx(1, 5), tx(1, 6)]);
+        assert_eq!(accepted.iter().map(|tx| tx.tx_hash).collect::<Vec<_>>(), vec![
+            tx(2, 0).tx_hash,
+            tx(1, 5).tx_hash,
+            tx(1, 6).tx_hash
+        ]);
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].tx_hash, tx(1, 7).tx_hash);
+        assert_eq!(dropped[0].reason, DropReason::SenderLimit { limit: 2 });
+
+        let (accepted, dropped) = InclusionPolicy::default().limit_per_sender(vec![tx(1, 0), tx(1, 1), tx(1, 2)]);
+        assert_eq!((accepted.len(), dropped.len()), (3, 0));
+    }
+
+    /// JSON-RPC node answering every request with `response`
+    async fn mock_node(response: serde_json::Value) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<serde_json::Value>| {
+                let mut response = response.clone();
+                async move {
+                    response["jsonrpc"] = "2.0".into();
+                    response["id"] = request["id"].clone();
+                    Json(response)
+                }
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        url
+    }
+
+    #[tokio::test]
+    pub async fn mempool_source_merge() {
+        let pending = |senders: &[u8]| {
+            let transactions = senders
+                .iter()
+                .map(|sender| {
+                    let tx = alloy::rpc::types::Transaction {
+                        hash: B256::repeat_byte(*sender),
+                        from: Address::with_last_byte(*sender),
+                        ..Default::default()
+                    };
+                    (tx.from.to_string(), serde_json::json!({ "0": tx }))
+                })
+                .collect::<serde_json::Map<_, _>>();
+            serde_json::json!({ "result": { "pending": transactions, "queued": {} } })
+        };
+        let hashes = |pending: Vec<Transaction>| pending.into_iter().map(|tx| tx.tx_hash).collect::<HashSet<_>>();
+
+        let a = mock_node(pending(&[1, 2])).await;
+        let b = mock_node(pending(&[2, 3])).await;
+        // Takes the connection but never answers
+        let silent = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let silent_url = format!("http://{}", silent.local_addr().unwrap());
+        // Nothing listens here anymore
+        let down_url = {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+
+        // The threshold is lowered to the two nodes that answered in time
+        let sources =
+            MempoolSources::new(&[a.clone(), b.clone(), down_url.clone(), silent_url], Aggregation::Threshold(3), false)
+                .unwrap();
+        assert_eq!(hashes(sources.pending(100).await.unwrap()), HashSet::from([B256::repeat_byte(2)]));
+        let health = sources.health();
+        assert!(health[0].healthy && health[1].healthy);
+        assert!(!health[2].healthy && !health[3].healthy);
+        assert!(health[3].last_error.as_ref().is_some_and(|err| err.contains("timed out")));
+        assert_eq!(health[0].last_success, Some(100));
+
+        let union = MempoolSources::new(&[down_url.clone(), a, b], Aggregation::Union, false).unwrap();
+        assert_eq!(hashes(union.pending(100).await.unwrap()), HashSet::from([1, 2, 3].map(B256::repeat_byte)));
+        assert_eq!(union.health()[0].consecutive_failures, 1);
+
+        // Only fails when no node answered
+        let down = MempoolSources::new(&[down_url], Aggregation::Union, false).unwrap();
+        assert!(down.pending(100).await.is_err());
+
+        assert!(MempoolSources::new(&[], Aggregation::Union, false).is_err());
+        assert!(MempoolSources::new(&["not a url".to_string()], Aggregation::Union, false).is_err());
+    }
+
+    #[tokio::test]
+    pub async fn simulation_failures() {
+        let transfer = |nonce: u64| Transaction { gas: 21_000, ..tx(1, nonce) };
+        let transactions = vec![transfer(0), transfer(1)];
+        let simulate = |url: String| {
+            let candidates = transactions.iter().collect::<Vec<_>>();
+            async move {
+                let provider = alloy::providers::ProviderBuilder::new().on_http(url.parse().unwrap());
+                let outcome = Simulator::new(&provider).simulate(candidates).await;
+                (outcome.executable.len(), outcome.unsimulated, outcome.dropped)
+            }
+        };
+
+        // A result missing calls is a node error, the candidates are kept unsimulated
+        let short = mock_node(serde_json::json!({
+            "result": [{ "calls": [{ "status": "0x1", "gasUsed": "0x5208" }] }]
+        }));
+        let (executable, unsimulated, dropped) = simulate(short.await).await;
+        assert_eq!((executable, unsimulated, dropped.len()), (2, 2, 0));
+
+        // So is a node that can't be reached
+        let (executable, unsimulated, dropped) = simulate("http://127.0.0.1:1".to_string()).await;
+        assert_eq!((executable, unsimulated, dropped.len()), (2, 2, 0));
+
+        // A rejected call drops it and the sender's later transactions
+        let rejected = mock_node(serde_json::json!({ "error": { "code": -38010, "message": "nonce too low" } }));
+        let (executable, unsimulated, dropped) = simulate(rejected.await).await;
+        assert_eq!((executable, unsimulated, dropped.len()), (0, 0, 2));
+        assert!(dropped.iter().all(|dropped| matches!(dropped.reason, DropReason::SimulationFailed { .. })));
+
+        // A node's internal error doesn't
+        let internal = mock_node(serde_json::json!({ "error": { "code": -32603, "message": "internal error" } }));
+        let (executable, unsimulated, dropped) = simulate(internal.await).await;
+        assert_eq!((executable, unsimulated, dropped.len()), (2, 2, 0));
+    }
+
+    #[tokio::test]
+    pub async fn nonce_lookup_failures() {
+        let transactions = vec![tx(1, 3), tx(1, 4), tx(2, 0)];
+
+        let url = mock_node(serde_json::json!({ "result": "0x3" })).await;
+        let provider = alloy::providers::ProviderBuilder::new().on_http(url.parse().unwrap());
+        let (ordered, dropped) = order_by_nonce(&provider, transactions.clone()).await;
+        assert_eq!(ordered.iter().map(|tx| tx.tx_hash).collect::<Vec<_>>(), vec![
+            transactions[0].tx_hash,
+            transactions[1].tx_hash
+        ]);
+        assert_eq!(dropped[0].reason, DropReason::StaleNonce { account_nonce: 3 });
+
+        // Only the senders whose lookup failed are dropped, here every sender's
+        let url = mock_node(serde_json::json!({ "error": { "code": -32603, "message": "internal error" } })).await;
+        let provider = alloy::providers::ProviderBuilder::new().on_http(url.parse().unwrap());
+        let (ordered, dropped) = order_by_nonce(&provider, transactions).await;
+        assert!(ordered.is_empty());
+        assert_eq!(dropped.len(), 3);
+        assert!(dropped.iter().all(|dropped| matches!(dropped.reason, DropReason::NonceLookupFailed { .. })));
+    }
+
+    #[test]
+    pub fn constraint_signing_root() {
+        // `{tx, blob_versioned_hashes}`, no longer the bare transaction hash of the `{tx}` container
+        let constraint = Constraint {
+            tx: [0x11; 32],
+            blob_versioned_hashes: vec![],
+        };
+        let empty_hashes = VariableList::<[u8; 32], U4096>::default().tree_hash_root();
+        assert_eq!(
+            constraint.tree_hash_root(),
+            tree_hash::merkle_root(&[[0x11; 32], empty_hashes.0].concat(), 2)
+        );
+        assert_ne!(constraint.tree_hash_root(), B256::repeat_byte(0x11));
+        assert_eq!(serde_json::to_value(&constraint).unwrap(), serde_json::json!({ "tx": "11".repeat(32) }));
+
+        // The signature commits to the blob hashes
+        let blob = Constraint {
+            tx: [0x11; 32],
+            blob_versioned_hashes: vec![B256::repeat_byte(0x01)],
+        };
+        assert_ne!(blob.tree_hash_root(), constraint.tree_hash_root());
+        let other_blob = Constraint {
+            blob_versioned_hashes: vec![B256::repeat_byte(0x02)],
+            ..blob.clone()
+        };
+        assert_ne!(other_blob.tree_hash_root(), blob.tree_hash_root());
+        assert!(serde_json::to_value(&blob).unwrap().get("blob_versioned_hashes").is_some());
+    }
+
+    #[test]
+    pub fn signed_list_carries_cached_transactions() {
+        let listed = |byte: u8| {
+            ListedTransaction::from(&Transaction {
+                tx_hash: B256::with_last_byte(byte),
+                bytes: Bytes::from(vec![byte; 32]),
+                ..Default::default()
+            })
+        };
+        let signed_constraints = |cached: &CachedInclusionList| cached.inclusion_list.constraints[0].to_vec();
+        let cached_constraints =
+            |cached: &CachedInclusionList| cached.transactions.iter().map(|tx| tx.constraint.clone()).collect::<Vec<_>>();
+
+        let cache = empty_cache();
+        let list = InclusionList::new(12, 1, vec![]);
+        let cached = CachedInclusionList::new(list.clone(), vec![listed(1), listed(2), listed(3)], false);
+        assert_eq!(signed_constraints(&cached).len(), 3);
+        assert_eq!(signed_constraints(&cached), cached_constraints(&cached));
+        cache.inclusion_list_cache.write().insert((12, InclusionListType::Spot), cached);
+
+        // Pins go ahead of the mempool derived transactions and are signed with them
+        cache.pin_transaction((12, InclusionListType::Spot), listed(4));
+        let cached = cache.inclusion_list_cache.read()[&(12, InclusionListType::Spot)].clone();
+        assert_eq!(cached.transactions[0].tx_hash, B256::with_last_byte(4));
+        assert_eq!(signed_constraints(&cached).len(), 4);
+        assert_eq!(signed_constraints(&cached), cached_constraints(&cached));
+
+        // A list over the limit is cut off in the cache as well, never only in the signed list
+        let transactions = (0..MAX_CONSTRAINTS_PER_LIST + 1).map(|index| listed(index as u8)).collect();
+        let cached = CachedInclusionList::new(list, transactions, false);
+        assert_eq!(cached.transactions.len(), MAX_CONSTRAINTS_PER_LIST);
+        assert_eq!(signed_constraints(&cached), cached_constraints(&cached));
+    }
+
+    #[test]
+    pub fn sanctions_file_parsing() {
+        let path = std::env::temp_dir().join(format!("il-boost-sanctions-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        std::fs::write(
+            path,
+            format!(
+                "# Sanctioned addresses\n\n{}  # trailing comment\n   \n{}\n{}\n",
+                Address::with_last_byte(1),
+                Address::with_last_byte(2),
+                Address::with_last_byte(1),
+            ),
+        )
+        .unwrap();
+        let sanctions = SanctionsList::load(path).unwrap();
+        assert!(!sanctions.is_empty());
+
+        std::fs::write(path, "# Only comments\n\n").unwrap();
+        assert!(SanctionsList::load(path).unwrap().is_empty());
+
+        // One bad line rejects the whole file rather than silently listing less
+        std::fs::write(path, format!("{}\n0x1234\n", Address::with_last_byte(1))).unwrap();
+        assert!(SanctionsList::load(path).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    pub async fn sanctions_tagging() {
+        let path = std::env::temp_dir().join(format!("il-boost-tagging-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        let [sanctioned, other] = [Address::with_last_byte(1), Address::with_last_byte(2)];
+        std::fs::write(path, format!("{sanctioned}\n")).unwrap();
+        let sanctions = SanctionsList::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let call = |sender: u8, to: Option<Address>, nonce: u64| Transaction { to, ..tx(sender, nonce) };
+        let tag = |exposure: Exposure| SanctionsTag {
+            address: sanctioned,
+            exposure,
+        };
+
+        assert_eq!(sanctions.direct_tags(&call(1, Some(other), 0)), vec![tag(Exposure::Sender)]);
+        assert_eq!(sanctions.direct_tags(&call(2, Some(sanctioned), 0)), vec![tag(Exposure::Recipient)]);
+        assert_eq!(sanctions.direct_tags(&call(1, Some(sanctioned), 1)), vec![
+            tag(Exposure::Sender),
+            tag(Exposure::Recipient)
+        ]);
+        assert!(sanctions.direct_tags(&call(2, None, 1)).is_empty());
+
+        // Untagged candidates are traced, tagged ones aren't
+        let trace = serde_json::json!({ "result": {
+            "from": other,
+            "to": other,
+            "calls": [{ "from": other, "to": other, "calls": [{ "from": other, "to": sanctioned }] }],
+        } });
+        let url = mock_node(trace).await;
+        let provider = alloy::providers::ProviderBuilder::new().on_http(url.parse().unwrap());
+        let candidates = vec![call(1, Some(other), 0), call(2, Some(other), 2)];
+
+        let tagged = sanctions.tag(&provider, &candidates, false).await;
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[&candidates[0].tx_hash], vec![tag(Exposure::Sender)]);
+
+        let tagged = sanctions.tag(&provider, &candidates, true).await;
+        assert_eq!(tagged.len(), 2);
+        assert_eq!(tagged[&candidates[1].tx_hash], vec![tag(Exposure::InternalCall)]);
+    }
+
+    #[test]
+    pub fn list_target_selection() {
+        let proposers = HashMap::from([(11, 1), (12, 2), (13, 3)]);
+        let proposer_of = |slot| proposers.get(&slot).copied();
+        let managed = |validator_index| validator_index == 1 || validator_index == 2;
+        let opted_in = HashSet::from([3]);
+        let target = |slot, validator_index, il_type| ListTarget {
+            slot,
+            validator_index,
+            il_type,
+            revision: 0,
+        };
+
+        // Proposer of 11 is ours, the forward list for 12 is signed by it
+        assert_eq!(select_list_targets(InclusionListMode::Spot, 10, proposer_of, managed, &opted_in), vec![
+            target(11, 1, InclusionListType::Spot)
+        ]);
+        assert_eq!(select_list_targets(InclusionListMode::Both, 10, proposer_of, managed, &opted_in), vec![
+            target(11, 1, InclusionListType::Spot),
+            target(12, 1, InclusionListType::Forward),
+        ]);
+
+        // The forward proposer of 13 opted in, an unknown forward proposer skips the forward list
+        assert_eq!(select_list_targets(InclusionListMode::Forward, 11, proposer_of, managed, &opted_in), vec![
+            target(13, 2, InclusionListType::Forward)
+        ]);
+        assert_eq!(select_list_targets(InclusionListMode::Forward, 11, proposer_of, managed, &HashSet::new()), vec![]);
+        assert_eq!(select_list_targets(InclusionListMode::Both, 12, proposer_of, managed, &opted_in), vec![]);
+    }
+
+    #[test]
+    pub fn list_cache_per_type() {
+        let cache = empty_cache();
+
+        // In `both` mode slot 12 has the spot list of its own proposer and the forward list of slot 11's
+        for (il_type, validator_index) in [(InclusionListType::Spot, 2), (InclusionListType::Forward, 1)] {
+            let list = InclusionList::new(12, validator_index, vec![]).with_type(il_type);
+            cache.inclusion_list_cache.write().insert((12, il_type), CachedInclusionList::new(list, vec![], false));
+        }
+
+        cache.set_frozen((12, InclusionListType::Forward), true);
+        let lists = cache.inclusion_list_cache.read();
+        assert!(lists[&(12, InclusionListType::Forward)].frozen);
+        assert!(!lists[&(12, InclusionListType::Spot)].frozen);
+        assert_eq!(lists[&(12, InclusionListType::Forward)].inclusion_list.validator_index, 1);
+        drop(lists);
+
+        cache.prune(13);
+        assert!(cache.inclusion_list_cache.read().is_empty());
+        assert!(cache.overrides.read().is_empty());
+    }
+
+    #[test]
+    pub fn revision_change_detection() {
+        let constraint = |byte| Constraint {
+            tx: [byte; 32],
+            blob_versioned_hashes: vec![],
+        };
+        let submitted = InclusionList::new(10, 1, vec![constraint(1)]);
+
+        // A failed revision never became the submitted list, so rebuilding it is still a change
+        let failed = submitted.with_constraints(vec![constraint(2)]).with_revision(1);
+        let retried = failed.clone().with_revision(2);
+        assert!(!retried.unchanged_from(&submitted));
+        assert!(submitted.clone().with_revision(3).unchanged_from(&submitted));
+        assert!(!submitted.clone().with_conditional(true).unchanged_from(&submitted));
+
+        // Revisions every 2s after 0 up to a cutoff at 7s, none once the cutoff passed
+        let schedule = |now, cutoff| {
+            let mut times = vec![];
+            let mut revisions = RevisionSchedule::new(Block::default(), vec![], now, cutoff, 2_000);
+            while let Some(due) = revisions {
+                times.push((due.revision, due.due));
+                revisions = due.advance(times.last().unwrap().1);
+            }
+            times
+        };
+        assert_eq!(schedule(0, 7_000), vec![(1, 2_000), (2, 4_000), (3, 6_000)]);
+        assert_eq!(schedule(0, 6_000), vec![(1, 2_000), (2, 4_000)]);
+        assert!(schedule(8_000, 7_000).is_empty());
+        assert!(RevisionSchedule::new(Block::default(), vec![], 0, 7_000, 0).is_none());
+
+        // A revision that ran past the next due time skips it
+        let revisions = RevisionSchedule::new(Block::default(), vec![], 0, 9_000, 2_000).unwrap();
+        let next = revisions.advance(4_500).unwrap();
+        assert_eq!((next.revision, next.due), (2, 6_000));
+    }
+
+    #[tokio::test]
+    pub async fn supervisor_restarts() {
+        use std::time::{Duration, Instant};
+
+        let health = std::sync::Arc::new(SidecarHealth::default());
+        let starts = std::sync::Arc::new(parking_lot::Mutex::new(vec![]));
+        let startup_delay = Duration::from_millis(200);
+
+        let start = {
+            let starts = starts.clone();
+            move || {
+                let starts = starts.clone();
+                async move {
+                    let run = {
+                        let mut starts = starts.lock();
+                        starts.push(Instant::now());
+                        starts.len()
+                    };
+                    match run {
+                        1 => panic!("processing the slot panicked"),
+                        2 => Err(InclusionListBoostError::from("sidecar failed".to_string())),
+                        _ => std::future::pending().await,
+                    }
+                }
+            }
+        };
+        let began = Instant::now();
+        let supervisor = tokio::spawn(restart_loop(health.clone(), startup_delay, Duration::from_millis(10), start));
+
+        while starts.lock().len() < 3 {
+            assert!(began.elapsed() < Duration::from_secs(5), "sidecar wasn't restarted");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // A panic and an error both restart the sidecar, without waiting for the startup delay again
+        let starts = starts.lock().clone();
+        assert!(starts[0] - began >= startup_delay);
+        assert!(starts[2] - starts[0] < startup_delay);
+        assert_eq!(health.restarts(), 2);
+        assert!(health.is_alive());
+
+        supervisor.abort();
+    }
+
+    #[test]
+    pub fn next_block_fees() {
+        let base_fee = 1_000_000_000;
+        let gas_limit = 30_000_000;
+
+        assert_eq!(next_base_fee(gas_limit / 2, gas_limit, base_fee), base_fee);
+        assert_eq!(next_base_fee(gas_limit, gas_limit, base_fee), 1_125_000_000);
+        assert_eq!(next_base_fee(0, gas_limit, base_fee), 875_000_000);
+
+        let cancun = BlobParams::CANCUN;
+        assert_eq!(cancun.next_excess_blob_gas(0, DATA_GAS_PER_BLOB), 0);
+        assert_eq!(cancun.next_excess_blob_gas(0, 6 * DATA_GAS_PER_BLOB), 3 * DATA_GAS_PER_BLOB);
+        assert_eq!(cancun.blob_base_fee(0), 1);
+        assert!(cancun.blob_base_fee(10 * 3_338_477) > 20_000);
+
+        // Prague targets 6 of 9 blobs, with a slower base fee update
+        let prague = BlobParams::PRAGUE;
+        assert_eq!(prague.next_excess_blob_gas(0, 6 * DATA_GAS_PER_BLOB), 0);
+        assert_eq!(prague.next_excess_blob_gas(0, 9 * DATA_GAS_PER_BLOB), 3 * DATA_GAS_PER_BLOB);
+        assert_eq!(prague.max_blob_gas(), 1_179_648);
+        assert!(prague.blob_base_fee(10 * 3_338_477) < cancun.blob_base_fee(10 * 3_338_477));
+
+        // The next block's fork decides, the earliest fork applies before any activation
+        let schedule = BlobSchedule::default();
+        assert_eq!(schedule.at(0), cancun);
+        assert_eq!(schedule.at(prague.timestamp - 1), cancun);
+        assert_eq!(schedule.at(prague.timestamp), prague);
+
+        let mut parent = Header::default();
+        parent.gas_limit = gas_limit;
+        parent.gas_used = gas_limit;
+        parent.base_fee_per_gas = Some(base_fee);
+        parent.timestamp = prague.timestamp - 12;
+        assert_eq!(schedule.after(&parent, 12), prague);
+        // On a chain with shorter slots the next block is still before the fork
+        assert_eq!(schedule.after(&parent, 6), cancun);
+
+        let fees = NextBlockFees::from_parent(&parent, 0, schedule.after(&parent, 12));
+        assert_eq!(fees.base_fee, 1_125_000_000);
+        assert_eq!(fees.blob_base_fee, None);
+
+        let fees_with_headroom = NextBlockFees::from_parent(&parent, 1, schedule.after(&parent, 12));
+        assert_eq!(fees_with_headroom.base_fee, 1_265_625_000);
+
+        let mut tx = Transaction {
+            max_fee_per_gas: Some(1_200_000_000),
+            ..Default::default()
+        };
+        assert!(fees.check(&tx).is_ok());
+        assert!(fees_with_headroom.check(&tx).is_err());
+
+        tx.max_fee_per_gas = Some(1_000_000_000);
+        assert!(fees.check(&tx).is_err());
+    }
+
+    #[test]
+    pub fn sender_nonce_ordering() {
+        let tipped = |nonce: u64, tip: u128| Transaction {
+            max_priority_fee_per_gas: Some(tip),
+            tx_hash: B256::with_last_byte(nonce as u8 * 10 + tip as u8),
+            ..tx(1, nonce)
+        };
+
+        // Out of order, one stale, one replacement and a gap after nonce 7
+        let transactions = vec![tipped(6, 1), tipped(4, 1), tipped(5, 1), tipped(5, 2), tipped(9, 1), tipped(7, 1)];
+        let (executable, dropped) = order_sender_transactions(5, transactions);
+
+        let nonces = executable.iter().map(|tx| tx.nonce).collect::<Vec<_>>();
+        assert_eq!(nonces, vec![5, 6, 7]);
+        assert_eq!(executable[0].max_priority_fee_per_gas, Some(2));
+
+        let reasons = dropped.into_iter().map(|dropped| dropped.reason).collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            vec![
+                DropReason::StaleNonce { account_nonce: 5 },
+                DropReason::DuplicateNonce,
+                DropReason::NonceGap { expected_nonce: 8 },
+            ]
+        );
+    }
+
+    #[test]
+    pub fn knapsack_selection() {
+        let sized = |sender: u8, nonce: u64, gas: u128| Transaction { gas, ..tx(sender, nonce) };
+        let transactions = vec![sized(1, 0, 70_000), sized(2, 0, 30_000), sized(2, 1, 30_000), sized(3, 0, 40_000)];
+        let candidates = transactions
+            .iter()
+            .map(|tx| Candidate {
+                tx,
+                evidence: CensorshipEvidence {
+                    blocks_skipped: 1,
+                    ..Default::default()
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let selected_hashes = |candidates: Vec<Candidate>| {
+            let mut hashes = select(candidates, 120_000, 0).iter().map(|c| c.tx.tx_hash).collect::<Vec<_>>();
+            hashes.sort();
+            hashes
+        };
+
+        // Both of sender 2's transactions and sender 3's beat the single large transaction
+        let selected = selected_hashes(candidates.clone());
+        assert_eq!(selected, vec![transactions[1].tx_hash, transactions[2].tx_hash, transactions[3].tx_hash]);
+
+        // Senders' order in the input doesn't change the result
+        let mut reordered = candidates[3..].to_vec();
+        reordered.extend_from_slice(&candidates[..3]);
+        assert_eq!(selected_hashes(reordered), selected);
+
+        // Everything fits
+        assert_eq!(select(candidates, 200_000, 0).len(), 4);
+    }
+
+    #[test]
+    pub fn censorship_history() {
+        let block = |number: u64, gas_used: u128, lowest_included_tip: Option<u128>| BlockRecord {
+            number,
+            timestamp: 1_000 + number * 12,
+            base_fee: 10,
+            gas_limit: 100_000,
+            gas_used,
+            lowest_included_tip,
+            ..Default::default()
+        };
+
+        let mut history = BlockHistory::new(3);
+        for record in [block(1, 0, None), block(2, 0, Some(1)), block(3, 0, Some(5)), block(4, 90_000, Some(1))] {
+            history.insert(record);
+        }
+        assert_eq!(history.records().iter().map(|block| block.number).collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(history.missing(6), vec![5]);
+
+        let tx = Transaction {
+            gas: 21_000,
+            max_fee_per_gas: Some(20),
+            max_priority_fee_per_gas: Some(2),
+            ..Default::default()
+        };
+
+        // Block 3 only included higher tips and block 4 had no room left
+        assert_eq!(history.blocks_skipped(&tx, None), 1);
+        // Not pending yet when block 2 was built
+        assert_eq!(history.blocks_skipped(&tx, Some(1_030)), 0);
+    }
+
+    #[test]
+    pub fn conditional_satisfaction() {
+        let listed = [21_000u128, 100_000]
+            .into_iter()
+            .enumerate()
+            .map(|(index, gas)| ListedTransaction {
+                tx_hash: B256::with_last_byte(index as u8),
+                raw: None,
+                gas,
+                constraint: Default::default(),
+            })
+            .collect::<Vec<_>>();
+        let first_only = HashSet::from([listed[0].tx_hash]);
+
+        // The omitted 100k gas transaction doesn't fit the 50k left
+        assert_eq!(
+            check_block(&listed, true, &first_only, 30_000_000, 29_950_000),
+            Satisfaction::BlockFull {
+                remaining_gas: 50_000,
+                smallest_omitted_gas: 100_000
+            }
+        );
+        assert!(!check_block(&listed, false, &first_only, 30_000_000, 29_950_000).is_satisfied());
+        assert!(!check_block(&listed, true, &first_only, 30_000_000, 15_000_000).is_satisfied());
+
+        let all = listed.iter().map(|tx| tx.tx_hash).collect();
+        assert_eq!(check_block(&listed, false, &all, 30_000_000, 0), Satisfaction::Satisfied);
+
+        // A header only proves satisfaction when no constraint fits at all
+        assert!(check_header(&listed, true, 30_000_000, 29_990_000).is_some());
+        assert!(check_header(&listed, true, 30_000_000, 29_950_000).is_none());
+        assert!(check_header(&listed, false, 30_000_000, 30_000_000).is_none());
+    }
+
+    #[test]
+    pub fn focil_inclusion_list() {
+        let raw = |size: usize| Bytes::from(vec![2u8; size]);
+        let raw_transactions = [raw(5_000), raw(4_000), raw(3_000)];
+
+        // The second transaction would exceed the byte limit, the third still fits
+        let list = FocilInclusionList::new(10, 1, B256::ZERO, &raw_transactions);
+        let sizes = list.transactions.iter().map(|tx| tx.len()).collect::<Vec<_>>();
+        assert_eq!(sizes, vec![5_000, 3_000]);
+        assert!(sizes.iter().sum::<usize>() <= MAX_BYTES_PER_INCLUSION_LIST);
+
+        let domain = compute_domain([0, 0, 0, 1], B256::ZERO);
+        assert_eq!(domain[..4], DOMAIN_INCLUSION_LIST_COMMITTEE);
+        assert_ne!(list.signing_root(domain), list.signing_root(compute_domain([0, 0, 0, 2], B256::ZERO)));
+
+        // The fork of the list's epoch decides the domain, not the fork at startup
+        let domain_inputs = DomainInputs::new(B256::ZERO, vec![(0, [0, 0, 0, 1]), (10, [0, 0, 0, 2])], 32);
+        assert_eq!(domain_inputs.domain(319), domain);
+        assert_eq!(domain_inputs.domain(320), compute_domain([0, 0, 0, 2], B256::ZERO));
+
+        // Epochs follow the chain's epoch length
+        let short_epochs = DomainInputs::new(B256::ZERO, vec![(0, [0, 0, 0, 1]), (10, [0, 0, 0, 2])], 8);
+        assert_eq!(short_epochs.epoch(80), 10);
+        assert_eq!(short_epochs.domain(79), domain);
+        assert_eq!(short_epochs.domain(80), compute_domain([0, 0, 0, 2], B256::ZERO));
+    }
+
+    #[test]
+    pub fn committee_aggregation() {
+        let [a, b, c] = [1u8, 2, 3].map(B256::repeat_byte);
+        let committee = || {
+            let mut committee = Committee::default();
+            committee.vote("local", [a, b]);
+            committee.vote("node-1", [a, c]);
+            committee.vote("node-2", [a, b, b]);
+            committee
+        };
+
+        let union = committee().aggregate(Aggregation::Union);
+        assert_eq!(union.len(), 3);
+        assert_eq!(union[&c], vec!["node-1".to_string()]);
+
+        let threshold = committee().aggregate(Aggregation::Threshold(2));
+        assert_eq!(threshold.len(), 2);
+        assert_eq!(threshold[&a], vec!["local".to_string(), "node-1".to_string(), "node-2".to_string()]);
+        assert_eq!(threshold[&b], vec!["local".to_string(), "node-2".to_string()]);
+    }
+
+    #[test]
+    pub fn execution_api_sources() {
+        let single: InclusionListConfig = toml::from_str(
+            r#"
+            beacon_api = "http://beacon:4000"
+            execution_api = "http://node-0:8545"
+            relay = "http://relay"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(single.execution_api, vec!["http://node-0:8545".to_string()]);
+        assert_eq!(single.mempool_aggregation, Aggregation::Union);
+
+        let quorum: InclusionListConfig = toml::from_str(
+            r#"
+            beacon_api = "http://beacon:4000"
+            execution_api = ["http://node-0:8545", "http://node-1:8545", "http://node-2:8545"]
+            relay = "http://relay"
+            mempool_aggregation = { threshold = 2 }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(quorum.execution_api.len(), 3);
+        assert_eq!(quorum.mempool_aggregation, Aggregation::Threshold(2));
+
+        let empty = toml::from_str::<InclusionListConfig>(
+            r#"
+            beacon_api = "http://beacon:4000"
+            execution_api = []
+            relay = "http://relay"
+            "#,
+        );
+        assert!(empty.is_err());
+    }
+
+    #[tokio::test]
+    pub async fn shared_nonce_lookup() {
+        let url = mock_node(serde_json::json!({ "result": "0x9" })).await;
+        let provider = alloy::providers::ProviderBuilder::new().on_http(url.parse().unwrap());
+
+        // Senders looked up for an earlier view aren't looked up again
+        let mut account_nonces = AccountNonces::from([(Address::with_last_byte(2), Ok(0))]);
+        lookup_nonces(&provider, [Address::with_last_byte(1), Address::with_last_byte(2)], &mut account_nonces).await;
+        assert_eq!(account_nonces[&Address::with_last_byte(1)], Ok(9));
+        assert_eq!(account_nonces[&Address::with_last_byte(2)], Ok(0));
+
+        // A txpool keyed by nonce strings lists "10" before "9"
+        let (ordered, dropped) = order_with_nonces(&account_nonces, vec![tx(1, 10), tx(1, 9), tx(2, 0), tx(3, 0)]);
+        assert_eq!(ordered.iter().map(|tx| (tx.from, tx.nonce)).collect::<Vec<_>>(), vec![
+            (Address::with_last_byte(1), 9),
+            (Address::with_last_byte(1), 10),
+            (Address::with_last_byte(2), 0),
+        ]);
+        assert_eq!(dropped.len(), 1);
+        assert!(matches!(dropped[0].reason, DropReason::NonceLookupFailed { .. }));
+    }
+
+    #[test]
+    pub fn delegation_lifecycle() {
+        let delegation = |slot: u64, delegate: u8| Delegation {
+            slot,
+            validator_index: 1,
+            validator_pubkey: BlsPublicKey::repeat_byte(1),
+            delegate_pubkey: BlsPublicKey::repeat_byte(delegate),
+            relay: "http://relay".to_string(),
+        };
+        let mut manager = DelegationManager::load(None).unwrap();
+
+        let to_send = manager.plan(vec![delegation(10, 1), delegation(11, 1)], 10);
+        assert_eq!(to_send.len(), 2);
+        to_send.into_iter().for_each(|d| manager.record_delegated(d));
+
+        // Identical delegations aren't sent again
+        assert!(manager.plan(vec![delegation(10, 1), delegation(11, 1)], 10).is_empty());
+
+        // A new delegate key revokes the old delegation, a dropped slot is revoked
+        let to_send = manager.plan(vec![delegation(10, 2)], 10);
+        assert_eq!(to_send, vec![delegation(10, 2)]);
+        assert_eq!(manager.pending_revocations(), vec![delegation(10, 1), delegation(11, 1)]);
+        manager.record_revoked(&delegation(11, 1));
+        assert_eq!(manager.pending_revocations(), vec![delegation(10, 1)]);
+
+        // Operator revoked slots are never delegated again
+        manager.record_delegated(delegation(10, 2));
+        manager.revoke_slot(10);
+        assert!(manager.delegations().is_empty());
+        assert!(manager.plan(vec![delegation(10, 2)], 10).is_empty());
+    }
+
+    #[test]
+    pub fn proxy_key_reuse() {
+        let key = |byte: u8| BlsPublicKey::repeat_byte(byte);
+        let delegation = |delegator: BlsPublicKey, proxy: BlsPublicKey| -> SignedProxyDelegation {
+            serde_json::from_value(serde_json::json!({
+                "message": { "delegator": delegator, "proxy": proxy },
+                "signature": format!("0x{}", "00".repeat(96)),
+            }))
+            .unwrap()
+        };
+        let validator_keys = HashMap::from([(1, key(1)), (2, key(2)), (3, key(3)), (4, key(4))]);
+        let saved = HashMap::from([
+            (1, delegation(key(1), key(11))),
+            // The signer lost the proxy key
+            (2, delegation(key(2), key(12))),
+            // Saved for another validator key at the same index
+            (3, delegation(key(5), key(13))),
+        ]);
+
+        let (reused, mut missing) = reusable_delegations(saved, &validator_keys, &[key(11), key(13)]);
+        assert_eq!(reused.keys().collect::<Vec<_>>(), vec![&1]);
+        assert_eq!(reused[&1].message.proxy, key(11));
+
+        missing.sort();
+        assert_eq!(missing, vec![(2, key(2)), (3, key(3)), (4, key(4))]);
+    }
+
+    #[test]
+    pub fn inclusion_list_revisions() {
+        let list = InclusionList::new(10, 1, vec![]).with_revision(3);
+        let revised = list.with_constraints(vec![Default::default()]);
+        assert_eq!(revised.revision, 3);
+
+        // The signature commits to the revision
+        assert_ne!(list.tree_hash_root(), list.clone().with_revision(4).tree_hash_root());
+    }
+
+    #[test]
+    pub fn signing_protection() {
+        let pubkey = BlsPublicKey::repeat_byte(1);
+        let [a, b, c] = [1u8, 2, 3].map(B256::repeat_byte);
+        let mut db = ProtectionDb::default();
+
+        db.check_inclusion_list(pubkey, 10, a, 0).unwrap();
+        db.check_inclusion_list(pubkey, 10, a, 0).unwrap();
+        assert!(db.check_inclusion_list(pubkey, 10, b, 0).is_err());
+        db.check_inclusion_list(pubkey, 10, b, 1).unwrap();
+        assert!(db.check_inclusion_list(pubkey, 10, c, 1).is_err());
+
+        db.check_delegation(pubkey, 10, a).unwrap();
+        assert!(db.check_delegation(pubkey, 10, b).is_err());
+        db.record_revocation(pubkey, 10);
+        db.check_delegation(pubkey, 10, b).unwrap();
+
+        // A second instance importing the history refuses the same conflicts
+        let mut other = ProtectionDb::default();
+        assert_eq!(other.import(db.export()).unwrap(), 2);
+        assert!(other.check_inclusion_list(pubkey, 10, c, 1).is_err());
+        assert!(other.check_delegation(pubkey, 10, c).is_err());
+
+        // Histories that double-signed a list revision or an unrevoked delegation aren't merged
+        let mut conflicting = ProtectionDb::default();
+        conflicting.check_inclusion_list(pubkey, 10, c, 1).unwrap();
+        assert!(matches!(
+            conflicting.import(db.export()),
+            Err(InclusionListBoostError::ConflictingSignature(_))
+        ));
+        let mut conflicting = ProtectionDb::default();
+        conflicting.check_delegation(pubkey, 10, c).unwrap();
+        assert!(matches!(
+            conflicting.import(db.export()),
+            Err(InclusionListBoostError::ConflictingSignature(_))
+        ));
+        assert!(conflicting.export().data[0].signed_inclusion_lists.is_empty());
+
+        // Pruned slots stay protected by the watermark, also on instances importing the history
+        db.check_inclusion_list(pubkey, 20, a, 0).unwrap();
+        db.prune(15);
+        assert_eq!(db.export().data[0].signed_inclusion_lists.len(), 1);
+        assert!(db.check_inclusion_list(pubkey, 10, c, 5).is_err());
+        let mut other = ProtectionDb::default();
+        other.import(db.export()).unwrap();
+        assert!(other.check_delegation(pubkey, 12, c).is_err());
+        other.check_inclusion_list(pubkey, 20, a, 0).unwrap();
+    }
+
+    #[test]
+    pub fn slot_clock_schedule() {
+        let clock = SlotClock::new(1_000, 12, 32);
+        let slot_10 = clock.slot_start_millis(10);
+        assert_eq!(slot_10, 1_120_000);
+        assert_eq!(clock.slot_at(slot_10 + 11_999), 10);
+        assert_eq!(clock.slot_at(0), 0);
+
+        // Builds at the offset into the current slot until it has passed, then into the next one
+        assert_eq!(clock.next_slot_offset(slot_10 + 1_000, 4_000), (10, slot_10 + 4_000));
+        assert_eq!(clock.next_slot_offset(slot_10 + 4_000, 4_000), (11, slot_10 + 16_000));
+    }
+
+    /// Beacon node serving `blocks` by slot or root, every other block is missing
+    async fn mock_beacon_blocks(blocks: HashMap<String, (StatusCode, String)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let app = Router::new().route(
+            "/eth/v2/beacon/blocks/:block_id",
+            axum::routing::get(move |axum::extract::Path(block_id): axum::extract::Path<String>| {
+                let response = blocks
+                    .get(&block_id)
+                    .cloned()
+                    .unwrap_or((StatusCode::NOT_FOUND, r#"{"code":404,"message":"NOT_FOUND"}"#.to_string()));
+                async move { response }
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        url
+    }
+
+    #[tokio::test]
+    pub async fn head_block_walk_back() {
+        let block = |block_number: u64| {
+            let body = serde_json::json!({
+                "data": { "message": { "body": { "execution_payload": { "block_number": block_number.to_string() } } } }
+            });
+            (StatusCode::OK, body.to_string())
+        };
+        let client = reqwest::Client::new();
+        let [head_root, orphaned_root] = [0xaa, 0xbb].map(B256::repeat_byte);
+
+        let beacon = mock_beacon_blocks(HashMap::from([
+            (head_root.to_string(), block(2_000)),
+            // Slot 100 was missed, 99 fails to load and 98 has the latest block
+            ("99".to_string(), (StatusCode::INTERNAL_SERVER_ERROR, "oops".to_string())),
+            ("98".to_string(), block(1_998)),
+            // Nothing from slot 150 until one slot further back than the walk goes
+            ((150 - MAX_MISSED_SLOTS - 1).to_string(), block(1_900)),
+            // Every slot around 300 fails
+            ("299".to_string(), (StatusCode::INTERNAL_SERVER_ERROR, "oops".to_string())),
+        ]))
+        .await;
+
+        // The root resolves the head directly, whatever its slot
+        assert_eq!(resolve_head_block_number(&client, &beacon, 100, head_root).await.unwrap(), Some(2_000));
+
+        // An unknown root walks back past missed and failed slots
+        assert_eq!(resolve_head_block_number(&client, &beacon, 100, orphaned_root).await.unwrap(), Some(1_998));
+        assert_eq!(resolve_head_block_number(&client, &beacon, 98, orphaned_root).await.unwrap(), Some(1_998));
+
+        // The walk stops after MAX_MISSED_SLOTS
+        assert_eq!(resolve_head_block_number(&client, &beacon, 150, orphaned_root).await.unwrap(), None);
+        assert_eq!(
+            resolve_head_block_number(&client, &beacon, 150 - 1, orphaned_root).await.unwrap(),
+            Some(1_900)
+        );
+
+        // Failures are only returned when no block was found
+        assert!(resolve_head_block_number(&client, &beacon, 300, orphaned_root).await.is_err());
+    }
 }